@@ -0,0 +1,63 @@
+// Service worker for the installable app.
+// Navigations are network-first, falling back to the last copy of the same page
+// and then to the home page; hashed build assets and versioned avatars are
+// cache-first. Server functions are never cached: offline note taps are queued
+// by the app itself.
+// v2 drops the v1 cache, which could hold another page under "/"
+const CACHE = "hot-dog-v2";
+const SHELL = ["/", "/manifest.webmanifest", "/icon.svg"];
+
+self.addEventListener("install", (event) => {
+  event.waitUntil(caches.open(CACHE).then((cache) => cache.addAll(SHELL)));
+  self.skipWaiting();
+});
+
+self.addEventListener("activate", (event) => {
+  event.waitUntil(
+    caches
+      .keys()
+      .then((keys) => Promise.all(keys.filter((k) => k !== CACHE).map((k) => caches.delete(k))))
+      .then(() => self.clients.claim())
+  );
+});
+
+self.addEventListener("fetch", (event) => {
+  const request = event.request;
+  if (request.method !== "GET") return;
+
+  const url = new URL(request.url);
+  if (url.origin !== self.location.origin || url.pathname.startsWith("/api/")) return;
+
+  if (request.mode === "navigate") {
+    event.respondWith(
+      fetch(request)
+        .then((response) => {
+          // Each page under its own path: pages are rendered on the server, and
+          // hydrating one page's HTML as another breaks the app
+          if (response.ok) {
+            const copy = response.clone();
+            caches.open(CACHE).then((cache) => cache.put(url.pathname, copy));
+          }
+          return response;
+        })
+        .catch(() => caches.match(url.pathname).then((cached) => cached || caches.match("/")))
+    );
+    return;
+  }
+
+  if (url.pathname.startsWith("/assets/") || url.pathname.startsWith("/avatars/")) {
+    event.respondWith(
+      caches.match(request).then(
+        (cached) =>
+          cached ||
+          fetch(request).then((response) => {
+            if (response.ok) {
+              const copy = response.clone();
+              caches.open(CACHE).then((cache) => cache.put(request, copy));
+            }
+            return response;
+          })
+      )
+    );
+  }
+});
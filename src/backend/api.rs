@@ -0,0 +1,212 @@
+//! The UI reaches the backend through [`Backend`]. Web builds call server functions;
+//! desktop and mobile builds (the `local` feature) run the same logic in-process
+//! against an on-device libSQL database.
+//!
+//! Each operation is listed once in the `operations!` invocation below, which
+//! generates the `Backend` method, the server function in `remote` and the
+//! in-process call made by `OnDevice`.
+#[cfg(feature = "local")]
+use crate::backend::repository::repository;
+#[cfg(feature = "local")]
+use crate::backend::{audit, avatars, backup, export, import, kids, report, stats};
+use crate::models::{
+    AuditAction, AuditEntry, BackupInfo, BackupStatus, CycleTotalsResponse, ExportFile,
+    ExportFormat, GetKidsResponse, HeatmapCell, ImportPreview, Kid, KidLimits, KidNoteRatio,
+    KidSummary, ReportFormat, ReportPeriod,
+};
+
+use dioxus::prelude::ServerFnError;
+
+/// The backend used by this build.
+#[cfg(not(feature = "local"))]
+pub type Api = ServerFns;
+#[cfg(feature = "local")]
+pub type Api = OnDevice;
+
+/// Talks to the server through the functions in `remote`.
+#[cfg(not(feature = "local"))]
+pub struct ServerFns;
+
+/// Runs the backend logic on the device itself.
+#[cfg(feature = "local")]
+pub struct OnDevice;
+
+/// Expands a list of `fn name(args) -> T = |repo| call;` entries. `call` is the
+/// shared function both backends run, with `repo` bound to `repository()`; entries
+/// that don't need the repository leave `|repo|` out.
+macro_rules! operations {
+    ($(
+        $(#[$attr:meta])*
+        fn $name:ident($($arg:ident: $ty:ty),* $(,)?) -> $ret:ty = $(|$repo:ident|)? $call:expr;
+    )*) => {
+        #[allow(async_fn_in_trait)]
+        pub trait Backend {
+            $(
+                $(#[$attr])*
+                async fn $name($($arg: $ty),*) -> Result<$ret, ServerFnError>;
+            )*
+        }
+
+        /// Server functions exposing the backend to web builds. Each one runs the
+        /// matching shared function on the server.
+        #[cfg(not(feature = "local"))]
+        pub mod remote {
+            #[cfg(feature = "server")]
+            use crate::backend::repository::repository;
+            #[cfg(feature = "server")]
+            use crate::backend::{audit, avatars, backup, export, import, kids, report, stats};
+            use crate::models::{
+                AuditAction, AuditEntry, BackupInfo, BackupStatus, CycleTotalsResponse,
+                ExportFile, ExportFormat, GetKidsResponse, HeatmapCell, ImportPreview, Kid,
+                KidLimits, KidNoteRatio, KidSummary, ReportFormat, ReportPeriod,
+            };
+            use dioxus::prelude::*;
+
+            $(
+                $(#[$attr])*
+                #[server]
+                pub async fn $name($($arg: $ty),*) -> Result<$ret, ServerFnError> {
+                    $(let $repo = repository().await;)?
+                    $call.await
+                }
+            )*
+        }
+
+        #[cfg(not(feature = "local"))]
+        impl Backend for ServerFns {
+            $(
+                $(#[$attr])*
+                async fn $name($($arg: $ty),*) -> Result<$ret, ServerFnError> {
+                    remote::$name($($arg),*).await
+                }
+            )*
+        }
+
+        #[cfg(feature = "local")]
+        impl Backend for OnDevice {
+            $(
+                $(#[$attr])*
+                async fn $name($($arg: $ty),*) -> Result<$ret, ServerFnError> {
+                    $(let $repo = repository().await;)?
+                    $call.await
+                }
+            )*
+        }
+    };
+}
+
+operations! {
+    /// Logs a +1 note, optionally backdated to `occurred_at` in the time zone
+    /// `utc_offset_minutes` ahead of UTC, and returns the kid with its updated count.
+    fn increment_kid_count(
+        kid_id: u32,
+        idempotency_key: Option<String>,
+        occurred_at: Option<chrono::NaiveDateTime>,
+        utc_offset_minutes: i32,
+    ) -> Kid = |repo| {
+        kids::increment_kid_count(repo, kid_id, idempotency_key, occurred_at, utc_offset_minutes)
+    };
+    /// Logs a -1 note, optionally backdated like `increment_kid_count`, and returns the
+    /// kid with its updated count.
+    fn decrement_kid_count(
+        kid_id: u32,
+        idempotency_key: Option<String>,
+        occurred_at: Option<chrono::NaiveDateTime>,
+        utc_offset_minutes: i32,
+    ) -> Kid = |repo| {
+        kids::decrement_kid_count(repo, kid_id, idempotency_key, occurred_at, utc_offset_minutes)
+    };
+    /// Kids with their counts for the cycle containing `period`, the current one by default.
+    fn get_kids(period: Option<chrono::NaiveDate>) -> GetKidsResponse =
+        |repo| kids::get_kids(repo, period);
+    /// Active kids, in display order.
+    fn list_kids() -> Vec<KidSummary> = |repo| kids::list_kids(repo);
+    /// Archived kids.
+    fn list_archived_kids() -> Vec<KidSummary> = |repo| kids::list_archived_kids(repo);
+    /// Adds a kid after validating the name and limits.
+    fn add_kid(name: String) -> KidSummary = |repo| kids::add_kid(repo, name);
+    /// Renames a kid.
+    fn rename_kid(kid_id: u32, new_name: String) -> () =
+        |repo| kids::rename_kid(repo, kid_id, new_name);
+    /// Stores a new display order.
+    fn reorder_kids(kid_ids: Vec<u32>) -> () = |repo| kids::reorder_kids(repo, kid_ids);
+    /// Updates a kid's color, emoji and birthdate.
+    fn update_kid_profile(
+        kid_id: u32,
+        color: Option<String>,
+        emoji: Option<String>,
+        birthdate: Option<chrono::NaiveDate>,
+    ) -> () = |repo| kids::update_kid_profile(repo, kid_id, color, emoji, birthdate);
+    /// Hides a kid, keeping their notes.
+    fn archive_kid(kid_id: u32) -> () = |repo| kids::archive_kid(repo, kid_id);
+    /// Brings an archived kid back.
+    fn restore_kid(kid_id: u32) -> () = |repo| kids::restore_kid(repo, kid_id);
+    /// Permanently deletes an archived kid and their notes.
+    fn delete_kid(kid_id: u32) -> () = |repo| kids::delete_kid(repo, kid_id);
+    /// Current aggregation (DAILY, WEEKLY, MONTHLY, YEARLY).
+    fn get_granularity() -> String = |repo| kids::get_granularity(repo);
+    /// Changes the aggregation.
+    fn update_granularity(granularity: String) -> () =
+        |repo| kids::update_granularity(repo, granularity);
+    /// Whether leaderboard mode is on.
+    fn get_leaderboard() -> bool = |repo| kids::get_leaderboard(repo);
+    /// Turns leaderboard mode on or off.
+    fn update_leaderboard(enabled: bool) -> () = |repo| kids::update_leaderboard(repo, enabled);
+    /// Whether notes may be backdated into closed cycles.
+    fn get_backdate_closed_cycles() -> bool = |repo| kids::get_backdate_closed_cycles(repo);
+    /// Allows or forbids backdating into closed cycles.
+    fn update_backdate_closed_cycles(enabled: bool) -> () =
+        |repo| kids::update_backdate_closed_cycles(repo, enabled);
+    /// Limits applied when adding or renaming kids.
+    fn get_kid_limits() -> KidLimits = |repo| kids::get_kid_limits(repo);
+    /// Changes the limits.
+    fn update_kid_limits(limits: KidLimits) -> () = |repo| kids::update_kid_limits(repo, limits);
+    /// Per-kid totals for the last `cycles` cycles.
+    fn get_cycle_totals(cycles: u32) -> CycleTotalsResponse =
+        |repo| stats::get_cycle_totals(repo, cycles);
+    /// Positive and negative note counts per kid.
+    fn get_note_ratios() -> Vec<KidNoteRatio> = |repo| stats::get_note_ratios(repo);
+    /// Note counts per weekday and hour, in the time zone `utc_offset_minutes` ahead of UTC.
+    fn get_activity_heatmap(utc_offset_minutes: i32) -> Vec<HeatmapCell> =
+        |repo| stats::get_activity_heatmap(repo, utc_offset_minutes);
+    /// A kid's week or month, `cycles_ago` cycles back, as a printable page or a PDF,
+    /// in the time zone `utc_offset_minutes` ahead of UTC.
+    fn kid_report(
+        kid_id: u32,
+        period: ReportPeriod,
+        cycles_ago: u32,
+        utc_offset_minutes: i32,
+        format: ReportFormat,
+    ) -> ExportFile = |repo| {
+        report::report_file(repo, kid_id, period, cycles_ago, utc_offset_minutes, format)
+    };
+    /// Stores a kid's photo and returns the new avatar version.
+    fn upload_kid_avatar(kid_id: u32, image: Vec<u8>) -> i64 =
+        |repo| avatars::upload_kid_avatar(repo, kid_id, image);
+    /// Removes a kid's photo.
+    fn remove_kid_avatar(kid_id: u32) -> () = |repo| avatars::remove_kid_avatar(repo, kid_id);
+    /// Latest audit log entries, newest first, filtered by action and by day (inclusive).
+    fn list_audit_log(
+        action: Option<AuditAction>,
+        from: Option<chrono::NaiveDate>,
+        to: Option<chrono::NaiveDate>,
+    ) -> Vec<AuditEntry> = |repo| audit::list_audit_log(repo, action, from, to);
+    /// All household data as a JSON document or a zip of CSV files.
+    fn export_data(format: ExportFormat) -> ExportFile = |repo| export::export_data(repo, format);
+    /// Imports a file in the export format; `dry_run` only previews the result.
+    fn import_data(bytes: Vec<u8>, dry_run: bool) -> ImportPreview =
+        |repo| import::import_data(repo, bytes, dry_run);
+    /// Backup configuration and the stored backups, newest first.
+    fn get_backup_status() -> BackupStatus = backup::get_backup_status();
+    /// Takes a backup right away.
+    fn back_up_now() -> BackupInfo = |repo| backup::back_up_now(repo);
+    /// Restores a stored backup. Only allowed while there are no kids.
+    fn restore_backup(name: String) -> () = |repo| backup::restore_backup(repo, name);
+    /// Debug builds only: the backend's current time.
+    #[cfg(debug_assertions)]
+    fn get_backend_time() -> chrono::NaiveDateTime = kids::get_backend_time();
+    /// Debug builds only: moves the backend clock to `target`, or back to now with `None`.
+    #[cfg(debug_assertions)]
+    fn travel_to(target: Option<chrono::NaiveDateTime>) -> chrono::NaiveDateTime =
+        |repo| kids::travel_to(repo, target);
+}
@@ -0,0 +1,85 @@
+//! Append-only record of who changed what. Every mutating backend function hands
+//! the repository an entry to write together with its change; entries are never
+//! edited or removed.
+use crate::backend::repository::{AuditRepository, NewAuditEntry};
+use crate::models::{AuditAction, AuditEntry};
+use chrono::{NaiveDate, TimeDelta};
+use dioxus::prelude::ServerFnError;
+use std::sync::OnceLock;
+
+/// Most entries returned by `list_audit_log`.
+const MAX_ENTRIES: u32 = 200;
+
+static ACTOR: OnceLock<&'static str> = OnceLock::new();
+
+/// Where changes come from. There are no user accounts, so the actor is the surface
+/// the change was made through: "web" or "app", unless a tool named itself.
+fn actor() -> &'static str {
+    if let Some(actor) = ACTOR.get() {
+        return actor;
+    }
+    if cfg!(feature = "local") {
+        "app"
+    } else {
+        "web"
+    }
+}
+
+/// Names the process making changes, e.g. "admin" for the command-line tool.
+pub fn set_actor(actor: &'static str) {
+    let _ = ACTOR.set(actor);
+}
+
+/// An entry for `action` made by this process. `kid` is the affected kid's id and
+/// current name.
+pub(crate) fn entry(
+    action: AuditAction,
+    kid: Option<(u32, &str)>,
+    before: Option<String>,
+    after: Option<String>,
+) -> NewAuditEntry {
+    NewAuditEntry {
+        actor: actor().to_string(),
+        action,
+        kid_id: kid.map(|(id, _)| id),
+        kid_name: kid.map(|(_, name)| name.to_string()),
+        before,
+        after,
+    }
+}
+
+/// Appends an entry for an event that changes nothing stored, like time travel.
+pub(crate) async fn record(
+    repo: &impl AuditRepository,
+    action: AuditAction,
+    kid: Option<(u32, &str)>,
+    before: Option<String>,
+    after: Option<String>,
+) -> Result<(), ServerFnError> {
+    repo.append(entry(action, kid, before, after)).await
+}
+
+/// Latest audit entries, newest first, optionally of one action and made between
+/// `from` and `to` (both days included).
+pub async fn list_audit_log(
+    repo: &impl AuditRepository,
+    action: Option<AuditAction>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> Result<Vec<AuditEntry>, ServerFnError> {
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            return Err(ServerFnError::new(
+                "The start date must not be after the end date".to_string(),
+            ));
+        }
+    }
+    let start = from.map(|day| day.and_hms_opt(0, 0, 0).unwrap());
+    let end = to.map(|day| (day + TimeDelta::days(1)).and_hms_opt(0, 0, 0).unwrap());
+    repo.audit_entries(action, start, end, MAX_ENTRIES).await
+}
+
+/// "on" or "off", for toggled settings.
+pub(crate) fn on_off(enabled: bool) -> String {
+    if enabled { "on" } else { "off" }.to_string()
+}
@@ -0,0 +1,243 @@
+#[cfg(any(feature = "server", feature = "local"))]
+use crate::backend::audit;
+#[cfg(any(feature = "server", feature = "local"))]
+use crate::backend::events::publish;
+#[cfg(any(feature = "server", feature = "local"))]
+use crate::backend::kids::find_kid;
+#[cfg(any(feature = "server", feature = "local"))]
+use crate::backend::repository::{
+    AuditRepository, AvatarsRepository, KidsRepository, NewAuditEntry,
+};
+#[cfg(feature = "server")]
+use crate::backend::repository::repository;
+#[cfg(any(feature = "server", feature = "local"))]
+use crate::models::{AuditAction, ChangeEvent};
+#[cfg(feature = "server")]
+use axum::{
+    extract::Path,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+#[cfg(any(feature = "server", feature = "local"))]
+use image::{codecs::jpeg::JpegEncoder, imageops::FilterType, ImageReader, Limits};
+#[cfg(any(feature = "server", feature = "local"))]
+use std::io::Cursor;
+#[cfg(any(feature = "server", feature = "local"))]
+use std::path::PathBuf;
+#[cfg(any(feature = "server", feature = "local"))]
+use std::sync::LazyLock;
+
+use dioxus::prelude::*;
+
+/// Largest upload accepted, before resizing.
+pub const MAX_AVATAR_BYTES: usize = 5 * 1024 * 1024;
+
+/// Stored avatars are square JPEGs of this many pixels per side.
+#[cfg(any(feature = "server", feature = "local"))]
+const AVATAR_SIZE: u32 = 256;
+
+/// Largest uploaded image decoded, in pixels per side and in bytes of memory.
+#[cfg(any(feature = "server", feature = "local"))]
+const MAX_DECODED_SIDE: u32 = 8192;
+#[cfg(any(feature = "server", feature = "local"))]
+const MAX_DECODED_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Where avatar images live. Set `AVATAR_DIR` to keep them on local disk;
+/// otherwise the repository stores them (as blobs in the `kid_avatars` table).
+#[cfg(any(feature = "server", feature = "local"))]
+pub enum AvatarStorage {
+    Libsql,
+    Disk(PathBuf),
+}
+
+#[cfg(any(feature = "server", feature = "local"))]
+static STORAGE: LazyLock<AvatarStorage> = LazyLock::new(|| match std::env::var("AVATAR_DIR") {
+    Ok(dir) if !dir.is_empty() => AvatarStorage::Disk(PathBuf::from(dir)),
+    _ => AvatarStorage::Libsql,
+});
+
+#[cfg(any(feature = "server", feature = "local"))]
+pub fn avatar_storage() -> &'static AvatarStorage {
+    &STORAGE
+}
+
+#[cfg(any(feature = "server", feature = "local"))]
+impl AvatarStorage {
+    fn file_path(dir: &std::path::Path, kid_id: u32) -> PathBuf {
+        dir.join(format!("{kid_id}.jpg"))
+    }
+
+    /// Stores a kid's photo and points their avatar version at it. The version is
+    /// only written once the photo is stored, so it never names a missing image.
+    pub async fn put(
+        &self,
+        repo: &(impl KidsRepository + AvatarsRepository),
+        kid_id: u32,
+        jpeg: Vec<u8>,
+        version: i64,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError> {
+        match self {
+            AvatarStorage::Libsql => repo.put_avatar(kid_id, jpeg, version, audit).await,
+            AvatarStorage::Disk(dir) => {
+                tokio::fs::create_dir_all(dir)
+                    .await
+                    .map_err(|e| ServerFnError::new(e.to_string()))?;
+                tokio::fs::write(Self::file_path(dir, kid_id), jpeg)
+                    .await
+                    .map_err(|e| ServerFnError::new(e.to_string()))?;
+                repo.set_avatar_version(kid_id, Some(version), audit).await
+            }
+        }
+    }
+
+    pub async fn get(
+        &self,
+        repo: &impl AvatarsRepository,
+        kid_id: u32,
+    ) -> Result<Option<Vec<u8>>, ServerFnError> {
+        match self {
+            AvatarStorage::Libsql => repo.avatar(kid_id).await,
+            AvatarStorage::Disk(dir) => match tokio::fs::read(Self::file_path(dir, kid_id)).await {
+                Ok(bytes) => Ok(Some(bytes)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(ServerFnError::new(e.to_string())),
+            },
+        }
+    }
+
+    /// Removes a kid's photo and clears their avatar version.
+    pub async fn delete(
+        &self,
+        repo: &(impl KidsRepository + AvatarsRepository),
+        kid_id: u32,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError> {
+        match self {
+            AvatarStorage::Libsql => repo.delete_avatar(kid_id, audit).await,
+            AvatarStorage::Disk(_) => {
+                // The version goes first: a leftover file is never served, while a
+                // version naming a missing file shows a broken image
+                repo.set_avatar_version(kid_id, None, audit).await?;
+                self.delete_file(kid_id).await
+            }
+        }
+    }
+
+    /// Removes a photo kept on disk. Photos stored as blobs go with their kid's row.
+    pub async fn delete_file(&self, kid_id: u32) -> Result<(), ServerFnError> {
+        if let AvatarStorage::Disk(dir) = self {
+            match tokio::fs::remove_file(Self::file_path(dir, kid_id)).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(ServerFnError::new(e.to_string())),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Decodes an uploaded image and crops/resizes it into a square JPEG.
+#[cfg(any(feature = "server", feature = "local"))]
+fn resize_avatar(bytes: &[u8]) -> Result<Vec<u8>, ServerFnError> {
+    let mut reader = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|e| ServerFnError::new(format!("Unsupported image: {e}")))?;
+    // A few kilobytes of compressed image can claim gigapixels; refuse those
+    // before anything is allocated
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DECODED_SIDE);
+    limits.max_image_height = Some(MAX_DECODED_SIDE);
+    limits.max_alloc = Some(MAX_DECODED_BYTES);
+    reader.limits(limits);
+    let img = reader
+        .decode()
+        .map_err(|e| ServerFnError::new(format!("Unsupported image: {e}")))?;
+    let resized = img
+        .resize_to_fill(AVATAR_SIZE, AVATAR_SIZE, FilterType::Lanczos3)
+        .to_rgb8();
+
+    let mut out = Vec::new();
+    JpegEncoder::new_with_quality(&mut out, 85)
+        .encode_image(&resized)
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    Ok(out)
+}
+
+/// Uploads a photo for a kid. The image is resized server-side before being stored.
+/// Returns the new avatar version, used to bust caches.
+#[cfg(any(feature = "server", feature = "local"))]
+pub async fn upload_kid_avatar(
+    repo: &(impl KidsRepository + AvatarsRepository + AuditRepository),
+    kid_id: u32,
+    image: Vec<u8>,
+) -> Result<i64, ServerFnError> {
+    if image.len() > MAX_AVATAR_BYTES {
+        return Err(ServerFnError::new("Image too large (max 5 MB)".to_string()));
+    }
+    // Checked first so an unknown kid never leaves an orphaned image behind
+    let kid = find_kid(repo, kid_id)
+        .await?
+        .ok_or_else(|| ServerFnError::new("Kid not found".to_string()))?;
+    let jpeg = resize_avatar(&image)?;
+
+    // A cache key rather than a point in the family's timeline: it must keep
+    // increasing even while the clock is time travelling
+    let version = chrono::offset::Utc::now().timestamp();
+    avatar_storage()
+        .put(
+            repo,
+            kid_id,
+            jpeg,
+            version,
+            audit::entry(AuditAction::AvatarUploaded, Some((kid.id, &kid.name)), None, None),
+        )
+        .await?;
+    publish(ChangeEvent::KidsChanged);
+    Ok(version)
+}
+
+/// Removes a kid's photo, falling back to the color/emoji avatar.
+#[cfg(any(feature = "server", feature = "local"))]
+pub async fn remove_kid_avatar(
+    repo: &(impl KidsRepository + AvatarsRepository + AuditRepository),
+    kid_id: u32,
+) -> Result<(), ServerFnError> {
+    let kid = find_kid(repo, kid_id)
+        .await?
+        .ok_or_else(|| ServerFnError::new("Kid not found".to_string()))?;
+    avatar_storage()
+        .delete(
+            repo,
+            kid_id,
+            audit::entry(AuditAction::AvatarRemoved, Some((kid.id, &kid.name)), None, None),
+        )
+        .await?;
+    publish(ChangeEvent::KidsChanged);
+    Ok(())
+}
+
+/// Routes serving stored avatars. URLs carry the avatar version, so responses are
+/// cached by browsers indefinitely.
+#[cfg(feature = "server")]
+pub fn routes() -> Router {
+    Router::new().route("/avatars/{kid_id}", get(serve_avatar))
+}
+
+#[cfg(feature = "server")]
+async fn serve_avatar(Path(kid_id): Path<u32>) -> Response {
+    match avatar_storage().get(repository().await, kid_id).await {
+        Ok(Some(bytes)) => (
+            [
+                (header::CONTENT_TYPE, "image/jpeg"),
+                (header::CACHE_CONTROL, "public, max-age=31536000, immutable"),
+            ],
+            bytes,
+        )
+            .into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
@@ -0,0 +1,299 @@
+//! Periodic backups. Each backup is a JSON export (see `export`) written to a local
+//! directory or an S3-compatible bucket; the oldest are deleted past the retention
+//! count. The database lives in Turso, so a snapshot of its contents is what gets
+//! copied rather than the database file.
+//!
+//! Configured from the environment: `BACKUP_DIR`, or `BACKUP_S3_BUCKET` with the
+//! usual `AWS_*` credentials and optionally `BACKUP_S3_ENDPOINT` (e.g. a local
+//! MinIO) and `BACKUP_S3_PREFIX`. `BACKUP_INTERVAL_HOURS` (24) and `BACKUP_KEEP`
+//! (14) tune the schedule and retention.
+use crate::backend::events::publish;
+use crate::backend::export::export_data;
+use crate::backend::import::{import_file, Restore};
+use crate::backend::kids::ALLOWED_GRANULARITIES;
+use crate::backend::repository::{
+    ImportRepository, KidsRepository, NotesRepository, Settings, SettingsRepository,
+};
+use crate::backend::validation::validate_limits;
+use crate::models::{
+    BackupInfo, BackupStatus, ChangeEvent, ExportFormat, HouseholdExport, KidLimits,
+};
+use chrono::{NaiveDateTime, Utc};
+use dioxus::logger::tracing;
+use dioxus::prelude::ServerFnError;
+use futures::TryStreamExt;
+use object_store::aws::AmazonS3Builder;
+use object_store::local::LocalFileSystem;
+use object_store::path::Path;
+use object_store::{ObjectStore, PutPayload};
+use std::sync::{Arc, LazyLock};
+
+const NAME_PREFIX: &str = "hot_dog-backup-";
+const NAME_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// Where backups are written, and how many are kept.
+pub struct Backups {
+    store: Arc<dyn ObjectStore>,
+    prefix: Path,
+    /// Shown on the settings screen.
+    target: String,
+    keep: usize,
+}
+
+static BACKUPS: LazyLock<Option<Backups>> = LazyLock::new(|| match Backups::from_env() {
+    Ok(backups) => backups,
+    Err(e) => {
+        tracing::warn!("Backups are disabled: {e}");
+        None
+    }
+});
+
+/// The configured backups, or `None` when backups are off.
+pub fn backups() -> Option<&'static Backups> {
+    BACKUPS.as_ref()
+}
+
+fn interval_hours() -> u64 {
+    env_number("BACKUP_INTERVAL_HOURS").unwrap_or(24).max(1)
+}
+
+fn env_number(name: &str) -> Option<u64> {
+    std::env::var(name).ok().and_then(|v| v.trim().parse().ok())
+}
+
+impl Backups {
+    pub fn new(store: Arc<dyn ObjectStore>, prefix: &str, target: String, keep: usize) -> Self {
+        Backups {
+            store,
+            prefix: Path::from(prefix),
+            target,
+            keep: keep.max(1),
+        }
+    }
+
+    fn from_env() -> Result<Option<Self>, String> {
+        let keep = env_number("BACKUP_KEEP").unwrap_or(14) as usize;
+        if let Ok(dir) = std::env::var("BACKUP_DIR") {
+            if !dir.is_empty() {
+                std::fs::create_dir_all(&dir).map_err(|e| format!("{dir}: {e}"))?;
+                let store = LocalFileSystem::new_with_prefix(&dir).map_err(|e| e.to_string())?;
+                return Ok(Some(Backups::new(Arc::new(store), "", dir, keep)));
+            }
+        }
+        if let Ok(bucket) = std::env::var("BACKUP_S3_BUCKET") {
+            if !bucket.is_empty() {
+                let mut builder = AmazonS3Builder::from_env().with_bucket_name(&bucket);
+                if let Ok(endpoint) = std::env::var("BACKUP_S3_ENDPOINT") {
+                    // Local stand-ins such as MinIO usually run over plain HTTP
+                    builder = builder
+                        .with_endpoint(&endpoint)
+                        .with_allow_http(endpoint.starts_with("http://"));
+                }
+                let store = builder.build().map_err(|e| e.to_string())?;
+                let prefix = std::env::var("BACKUP_S3_PREFIX").unwrap_or_default();
+                let target = format!("s3://{bucket}/{prefix}");
+                return Ok(Some(Backups::new(Arc::new(store), &prefix, target, keep)));
+            }
+        }
+        Ok(None)
+    }
+
+    fn path(&self, name: &str) -> Path {
+        self.prefix.child(name)
+    }
+
+    /// Writes a backup of the current data, then deletes the oldest past `keep`.
+    pub async fn back_up(
+        &self,
+        repo: &(impl KidsRepository + NotesRepository + SettingsRepository),
+    ) -> Result<BackupInfo, ServerFnError> {
+        // The real time, not `clock::now()`: names order the backups for retention,
+        // and a time-travelled clock would put new backups before old ones
+        let created_at = Utc::now().naive_utc();
+        let name = format!("{NAME_PREFIX}{}.json", created_at.format(NAME_FORMAT));
+        let file = export_data(repo, ExportFormat::Json).await?;
+        let size = file.bytes.len() as u64;
+        self.store
+            .put(&self.path(&name), PutPayload::from(file.bytes))
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+
+        for old in self.list().await?.into_iter().skip(self.keep) {
+            self.store
+                .delete(&self.path(&old.name))
+                .await
+                .map_err(|e| ServerFnError::new(e.to_string()))?;
+        }
+        Ok(BackupInfo {
+            name,
+            size,
+            created_at,
+        })
+    }
+
+    /// Stored backups, newest first. Other files next to them are left alone.
+    pub async fn list(&self) -> Result<Vec<BackupInfo>, ServerFnError> {
+        let objects: Vec<_> = self
+            .store
+            .list(Some(&self.prefix))
+            .try_collect()
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+
+        let mut backups: Vec<BackupInfo> = objects
+            .into_iter()
+            .filter_map(|meta| {
+                let name = meta.location.filename()?.to_string();
+                let stamp = name.strip_prefix(NAME_PREFIX)?.strip_suffix(".json")?;
+                let created_at = NaiveDateTime::parse_from_str(stamp, NAME_FORMAT).ok()?;
+                Some(BackupInfo {
+                    name,
+                    size: meta.size,
+                    created_at,
+                })
+            })
+            .collect();
+        backups.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(backups)
+    }
+
+    /// Reads a stored backup.
+    pub async fn read(&self, name: &str) -> Result<Vec<u8>, ServerFnError> {
+        if !name.starts_with(NAME_PREFIX) || name.contains('/') {
+            return Err(ServerFnError::new(format!("Not a backup: '{name}'")));
+        }
+        let bytes = self
+            .store
+            .get(&self.path(name))
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?
+            .bytes()
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        Ok(bytes.to_vec())
+    }
+}
+
+/// Server builds: backs up every `BACKUP_INTERVAL_HOURS`, the first time one
+/// interval after the newest stored backup, so restarting the server doesn't add
+/// backups that push older ones out. Does nothing when backups are off. Safe to
+/// call more than once.
+#[cfg(feature = "server")]
+pub fn start_schedule() {
+    use crate::backend::repository::repository;
+    use std::sync::Once;
+
+    static STARTED: Once = Once::new();
+    let Some(backups) = backups() else {
+        return;
+    };
+    STARTED.call_once(|| {
+        tokio::spawn(async move {
+            let period = std::time::Duration::from_secs(interval_hours() * 3600);
+            let since_latest = match backups.list().await {
+                // A backup stamped ahead of now counts as just taken
+                Ok(stored) => stored.first().map(|latest| {
+                    (Utc::now().naive_utc() - latest.created_at)
+                        .to_std()
+                        .unwrap_or_default()
+                }),
+                Err(e) => {
+                    tracing::warn!("Could not list backups: {e}");
+                    None
+                }
+            };
+            // Overdue, or none yet: right away
+            let first = period.saturating_sub(since_latest.unwrap_or(period));
+            let start = tokio::time::Instant::now() + first;
+            let mut ticks = tokio::time::interval_at(start, period);
+            loop {
+                ticks.tick().await;
+                if let Err(e) = backups.back_up(repository().await).await {
+                    tracing::error!("Backup failed: {e}");
+                }
+            }
+        });
+    });
+}
+
+/// Where backups go, the schedule, and the stored backups.
+pub async fn get_backup_status() -> Result<BackupStatus, ServerFnError> {
+    let Some(backups) = backups() else {
+        return Ok(BackupStatus {
+            target: None,
+            interval_hours: interval_hours(),
+            keep: 0,
+            backups: Vec::new(),
+        });
+    };
+    Ok(BackupStatus {
+        target: Some(backups.target.clone()),
+        interval_hours: interval_hours(),
+        keep: backups.keep as u32,
+        backups: backups.list().await?,
+    })
+}
+
+/// Takes a backup right away.
+pub async fn back_up_now(
+    repo: &(impl KidsRepository + NotesRepository + SettingsRepository),
+) -> Result<BackupInfo, ServerFnError> {
+    let backups =
+        backups().ok_or_else(|| ServerFnError::new("Backups are not configured".to_string()))?;
+    backups.back_up(repo).await
+}
+
+/// Restores the configured backup `name`. See `restore`.
+pub async fn restore_backup(
+    repo: &(impl KidsRepository + SettingsRepository + ImportRepository),
+    name: String,
+) -> Result<(), ServerFnError> {
+    let backups =
+        backups().ok_or_else(|| ServerFnError::new("Backups are not configured".to_string()))?;
+    let bytes = backups.read(&name).await?;
+    restore(repo, &name, bytes).await
+}
+
+/// Loads a backup into an empty database: its settings are applied and its kids
+/// and notes imported. Refused once any kid exists, so a restore never mixes with
+/// newer data.
+pub async fn restore(
+    repo: &(impl KidsRepository + SettingsRepository + ImportRepository),
+    name: &str,
+    bytes: Vec<u8>,
+) -> Result<(), ServerFnError> {
+    if !repo.list_active().await?.is_empty() || !repo.list_archived().await?.is_empty() {
+        return Err(ServerFnError::new(
+            "Backups can only be restored into an empty database".to_string(),
+        ));
+    }
+    let export: HouseholdExport =
+        serde_json::from_slice(&bytes).map_err(|e| ServerFnError::new(e.to_string()))?;
+
+    let settings = export.settings;
+    if !ALLOWED_GRANULARITIES.contains(&settings.granularity.as_str()) {
+        return Err(ServerFnError::new(format!(
+            "Invalid granularity in backup: '{}'",
+            settings.granularity
+        )));
+    }
+    let limits = KidLimits {
+        max_kids: settings.max_kids,
+        max_name_length: settings.max_name_length,
+    };
+    validate_limits(&limits)?;
+    let settings = Settings {
+        granularity: settings.granularity,
+        leaderboard: settings.leaderboard,
+        limits,
+        backdate_closed_cycles: settings.backdate_closed_cycles,
+    };
+
+    // Settings and data land in one transaction, so a failed restore changes nothing
+    let result = import_file(repo, &bytes, false, Some(Restore { name, settings })).await?;
+    if !result.errors.is_empty() {
+        return Err(ServerFnError::new(result.errors.join("; ")));
+    }
+    publish(ChangeEvent::SettingsChanged);
+    Ok(())
+}
@@ -0,0 +1,90 @@
+//! Current time as seen by the backend. Cycle computations and stored timestamps
+//! read it from here, so tests can pin it and debug builds can time travel.
+use chrono::{NaiveDate, NaiveDateTime};
+use std::cell::RefCell;
+use std::sync::{Arc, LazyLock, RwLock};
+
+pub trait Clock: Send + Sync {
+    /// The current time, in UTC.
+    fn now(&self) -> NaiveDateTime;
+}
+
+/// The system clock.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> NaiveDateTime {
+        chrono::offset::Utc::now().naive_utc()
+    }
+}
+
+/// A clock stopped at a given time.
+#[cfg(test)]
+pub struct FixedClock(pub NaiveDateTime);
+
+#[cfg(test)]
+impl Clock for FixedClock {
+    fn now(&self) -> NaiveDateTime {
+        self.0
+    }
+}
+
+/// The system clock moved by a fixed offset, so time keeps passing after a jump.
+#[cfg(debug_assertions)]
+struct OffsetClock(chrono::TimeDelta);
+
+#[cfg(debug_assertions)]
+impl Clock for OffsetClock {
+    fn now(&self) -> NaiveDateTime {
+        SystemClock.now() + self.0
+    }
+}
+
+static CLOCK: LazyLock<RwLock<Arc<dyn Clock>>> = LazyLock::new(|| RwLock::new(Arc::new(SystemClock)));
+
+thread_local! {
+    // Tests run in parallel, so each one pins the clock for its own thread only
+    static THREAD_CLOCK: RefCell<Option<Arc<dyn Clock>>> = const { RefCell::new(None) };
+}
+
+/// The current UTC time from the clock in use.
+pub fn now() -> NaiveDateTime {
+    if let Some(now) = THREAD_CLOCK.with(|clock| clock.borrow().as_ref().map(|c| c.now())) {
+        return now;
+    }
+    CLOCK.read().unwrap_or_else(|e| e.into_inner()).now()
+}
+
+/// The current UTC date.
+pub fn today() -> NaiveDate {
+    now().date()
+}
+
+/// Replaces the clock for the whole process.
+#[cfg(debug_assertions)]
+fn set_clock(clock: Arc<dyn Clock>) {
+    *CLOCK.write().unwrap_or_else(|e| e.into_inner()) = clock;
+}
+
+/// Replaces the clock for the current thread; `None` goes back to the process clock.
+#[cfg(test)]
+pub fn set_thread_clock(clock: Option<Arc<dyn Clock>>) {
+    THREAD_CLOCK.with(|current| *current.borrow_mut() = clock);
+}
+
+/// Pins the current thread's clock at `now`; `None` unpins it.
+#[cfg(test)]
+pub fn set_now(now: Option<NaiveDateTime>) {
+    set_thread_clock(now.map(|now| Arc::new(FixedClock(now)) as Arc<dyn Clock>));
+}
+
+/// Debug builds only: moves the backend to `target` (time keeps running from there),
+/// or back to the real time with `None`. Meant for checking cycle rollovers by hand.
+#[cfg(debug_assertions)]
+pub fn travel_to(target: Option<NaiveDateTime>) -> NaiveDateTime {
+    match target {
+        Some(target) => set_clock(Arc::new(OffsetClock(target - SystemClock.now()))),
+        None => set_clock(Arc::new(SystemClock)),
+    }
+    now()
+}
@@ -0,0 +1,39 @@
+use crate::models::ChangeEvent;
+#[cfg(any(feature = "server", feature = "local"))]
+use std::sync::LazyLock;
+#[cfg(any(feature = "server", feature = "local"))]
+use tokio::sync::broadcast;
+
+use dioxus::fullstack::ServerEvents;
+use dioxus::prelude::*;
+
+/// Fan-out of changes to every connected device. A deployment serves a single family,
+/// so one channel is enough.
+#[cfg(any(feature = "server", feature = "local"))]
+static CHANGES: LazyLock<broadcast::Sender<ChangeEvent>> =
+    LazyLock::new(|| broadcast::channel(64).0);
+
+/// Tells connected devices that something changed. Nobody listening is not an error.
+#[cfg(any(feature = "server", feature = "local"))]
+pub fn publish(event: ChangeEvent) {
+    let _ = CHANGES.send(event);
+}
+
+/// Streams changes made from any device as server-sent events.
+#[get("/api/changes")]
+pub async fn subscribe_changes() -> Result<ServerEvents<ChangeEvent>, ServerFnError> {
+    let mut rx = CHANGES.subscribe();
+    Ok(ServerEvents::new(move |mut tx| async move {
+        loop {
+            let event = match rx.recv().await {
+                Ok(event) => event,
+                // This client fell behind and missed events; make it reload everything
+                Err(broadcast::error::RecvError::Lagged(_)) => ChangeEvent::KidsChanged,
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            if tx.send(event).await.is_err() {
+                break;
+            }
+        }
+    }))
+}
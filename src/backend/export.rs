@@ -0,0 +1,160 @@
+//! Exports everything the household has stored, as one JSON document or as a zip
+//! of CSV files (one per table) for spreadsheets.
+use crate::backend::clock;
+use crate::backend::kids::FORMAT_MAP;
+use crate::backend::repository::{KidsRepository, NotesRepository, SettingsRepository};
+use crate::backend::stats::cycle_bounds;
+use crate::models::{
+    CycleRecord, ExportFile, ExportFormat, HouseholdExport, KidRecord, NoteRecord,
+    SettingsRecord, EXPORT_FORMAT_VERSION,
+};
+use dioxus::prelude::ServerFnError;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+/// Collects settings, kids (archived included), notes and per-cycle totals.
+pub async fn build_export(
+    repo: &(impl KidsRepository + NotesRepository + SettingsRepository),
+) -> Result<HouseholdExport, ServerFnError> {
+    let settings = repo.settings().await?;
+
+    let active = repo.list_active().await?;
+    let archived = repo.list_archived().await?;
+    let kids = active
+        .into_iter()
+        .map(|kid| (kid, false))
+        .chain(archived.into_iter().map(|kid| (kid, true)))
+        .map(|(kid, archived)| KidRecord {
+            id: kid.id,
+            name: kid.name,
+            position: kid.position,
+            color: kid.color,
+            emoji: kid.emoji,
+            birthdate: kid.birthdate,
+            archived,
+        })
+        .collect();
+
+    let notes = repo.all_notes().await?;
+    let cycles = cycle_records(&settings.granularity, &notes)?;
+
+    Ok(HouseholdExport {
+        format_version: EXPORT_FORMAT_VERSION,
+        exported_at: clock::now(),
+        settings: SettingsRecord {
+            granularity: settings.granularity,
+            leaderboard: settings.leaderboard,
+            backdate_closed_cycles: settings.backdate_closed_cycles,
+            max_kids: settings.limits.max_kids,
+            max_name_length: settings.limits.max_name_length,
+        },
+        kids,
+        notes,
+        cycles,
+    })
+}
+
+/// Totals per cycle and kid, oldest cycle first, for the cycles that have notes.
+fn cycle_records(
+    granularity: &str,
+    notes: &[NoteRecord],
+) -> Result<Vec<CycleRecord>, ServerFnError> {
+    let grain_format = *FORMAT_MAP
+        .get(granularity)
+        .ok_or_else(|| ServerFnError::new("Invalid granularity in settings".to_string()))?;
+
+    let mut totals: BTreeMap<(chrono::NaiveDateTime, u32), (chrono::NaiveDateTime, i32)> =
+        BTreeMap::new();
+    for note in notes {
+        let (start, end) = cycle_bounds(granularity, note.occurred_at.date())?;
+        totals.entry((start, note.kid_id)).or_insert((end, 0)).1 += note.quantity;
+    }
+
+    Ok(totals
+        .into_iter()
+        .map(|((start, kid_id), (end, total))| CycleRecord {
+            period: start.format(grain_format).to_string(),
+            start: start.date(),
+            end: end.date(),
+            kid_id,
+            total,
+        })
+        .collect())
+}
+
+/// Builds the export and encodes it as a downloadable file.
+pub async fn export_data(
+    repo: &(impl KidsRepository + NotesRepository + SettingsRepository),
+    format: ExportFormat,
+) -> Result<ExportFile, ServerFnError> {
+    let export = build_export(repo).await?;
+    let stem = format!("hot_dog-export-{}", export.exported_at.format("%Y%m%d-%H%M%S"));
+    match format {
+        ExportFormat::Json => Ok(ExportFile {
+            file_name: format!("{stem}.json"),
+            content_type: "application/json".to_string(),
+            bytes: serde_json::to_vec_pretty(&export)
+                .map_err(|e| ServerFnError::new(e.to_string()))?,
+        }),
+        ExportFormat::CsvZip => Ok(ExportFile {
+            file_name: format!("{stem}.zip"),
+            content_type: "application/zip".to_string(),
+            bytes: csv_zip(&export)?,
+        }),
+    }
+}
+
+/// File names inside the CSV zip.
+pub const SETTINGS_CSV: &str = "settings.csv";
+pub const KIDS_CSV: &str = "kids.csv";
+pub const NOTES_CSV: &str = "notes.csv";
+pub const CYCLES_CSV: &str = "cycles.csv";
+pub const MANIFEST_CSV: &str = "manifest.csv";
+
+#[derive(Serialize)]
+struct ManifestRecord {
+    format_version: u32,
+    exported_at: chrono::NaiveDateTime,
+}
+
+fn csv_zip(export: &HouseholdExport) -> Result<Vec<u8>, ServerFnError> {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let manifest = ManifestRecord {
+        format_version: export.format_version,
+        exported_at: export.exported_at,
+    };
+    add_csv(&mut zip, MANIFEST_CSV, std::slice::from_ref(&manifest))?;
+    add_csv(&mut zip, SETTINGS_CSV, std::slice::from_ref(&export.settings))?;
+    add_csv(&mut zip, KIDS_CSV, &export.kids)?;
+    add_csv(&mut zip, NOTES_CSV, &export.notes)?;
+    add_csv(&mut zip, CYCLES_CSV, &export.cycles)?;
+    let cursor = zip
+        .finish()
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    Ok(cursor.into_inner())
+}
+
+fn add_csv<W: Write + std::io::Seek, T: Serialize>(
+    zip: &mut zip::ZipWriter<W>,
+    name: &str,
+    records: &[T],
+) -> Result<(), ServerFnError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for record in records {
+        writer
+            .serialize(record)
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    zip.start_file(name, options)
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    zip.write_all(&bytes)
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    Ok(())
+}
@@ -0,0 +1,341 @@
+//! Loads history from a file in the export format (see `export`), e.g. when moving
+//! from a paper chart or another app. Settings and cycle totals in the file are
+//! ignored; kids are matched to existing ones by name and notes are added to them.
+use crate::backend::audit;
+use crate::backend::clock;
+use crate::backend::events::publish;
+use crate::backend::export::{KIDS_CSV, MANIFEST_CSV, NOTES_CSV};
+use crate::backend::kids::check_profile;
+use crate::backend::repository::{
+    ImportBatch, ImportRepository, ImportedKid, ImportedNote, KidsRepository, Settings,
+    SettingsRepository,
+};
+use crate::backend::validation::{
+    ensure_room_for_kid, ensure_unique_name, same_name, validate_kid_name,
+};
+use crate::models::{
+    AuditAction, ChangeEvent, ImportPreview, EXPORT_FORMAT_VERSION, MAX_IMPORT_BYTES,
+};
+use chrono::{NaiveDate, NaiveDateTime};
+use dioxus::prelude::ServerFnError;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+
+/// Errors listed in a preview before the rest are summed up.
+const MAX_ERRORS: usize = 50;
+
+/// A kid as read from the file. Only `id` and `name` are required, so a
+/// hand-written sheet can leave the rest out.
+#[derive(Deserialize)]
+struct KidRow {
+    id: u32,
+    name: String,
+    #[serde(default)]
+    color: Option<String>,
+    #[serde(default)]
+    emoji: Option<String>,
+    #[serde(default)]
+    birthdate: Option<NaiveDate>,
+    #[serde(default)]
+    archived: bool,
+}
+
+/// A note as read from the file. `created_at` defaults to the import time.
+#[derive(Deserialize)]
+struct NoteRow {
+    kid_id: u32,
+    quantity: i32,
+    occurred_at: NaiveDateTime,
+    #[serde(default)]
+    created_at: Option<NaiveDateTime>,
+    #[serde(default)]
+    idempotency_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Document {
+    #[serde(default)]
+    format_version: Option<u32>,
+    kids: Vec<KidRow>,
+    #[serde(default)]
+    notes: Vec<NoteRow>,
+}
+
+#[derive(Deserialize)]
+struct ManifestRow {
+    format_version: u32,
+}
+
+/// Reads a JSON export or a zip of CSV files, told apart by the zip signature.
+fn parse(bytes: &[u8]) -> Result<Document, String> {
+    if bytes.starts_with(b"PK\x03\x04") {
+        parse_csv_zip(bytes)
+    } else {
+        serde_json::from_slice(bytes).map_err(|e| format!("Invalid JSON: {e}"))
+    }
+}
+
+fn parse_csv_zip(bytes: &[u8]) -> Result<Document, String> {
+    let mut zip = zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| format!("Invalid zip: {e}"))?;
+    let format_version = match read_csv::<ManifestRow>(&mut zip, MANIFEST_CSV, false)? {
+        rows if rows.is_empty() => None,
+        rows => Some(rows[0].format_version),
+    };
+    Ok(Document {
+        format_version,
+        kids: read_csv(&mut zip, KIDS_CSV, true)?,
+        notes: read_csv(&mut zip, NOTES_CSV, false)?,
+    })
+}
+
+/// Reads every row of `name` in the zip. A missing file reads as no rows unless `required`.
+/// Files unzip to at most `MAX_IMPORT_BYTES`, so a small zip can't fill the memory.
+fn read_csv<T: DeserializeOwned>(
+    zip: &mut zip::ZipArchive<Cursor<&[u8]>>,
+    name: &str,
+    required: bool,
+) -> Result<Vec<T>, String> {
+    let mut contents = Vec::new();
+    match zip.by_name(name) {
+        Ok(file) => {
+            // One byte over the limit tells a file at the limit from a larger one
+            file.take(MAX_IMPORT_BYTES as u64 + 1)
+                .read_to_end(&mut contents)
+                .map_err(|e| format!("{name}: {e}"))?;
+            if contents.len() > MAX_IMPORT_BYTES {
+                return Err(format!("{name}: larger than 20 MB unzipped"));
+            }
+        }
+        Err(zip::result::ZipError::FileNotFound) if !required => return Ok(Vec::new()),
+        Err(e) => return Err(format!("{name}: {e}")),
+    }
+    csv::Reader::from_reader(contents.as_slice())
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("{name}: {e}"))
+}
+
+/// Validates the file and imports it in one transaction. With `dry_run` the
+/// transaction is rolled back, so the preview says exactly what an import would do.
+/// Nothing is written when the file has problems; they are listed in the preview.
+///
+/// Quantities must be +1 or -1 and notes can't be in the future. The backdating
+/// limits don't apply: importing history is the point.
+pub async fn import_data(
+    repo: &(impl KidsRepository + SettingsRepository + ImportRepository),
+    bytes: Vec<u8>,
+    dry_run: bool,
+) -> Result<ImportPreview, ServerFnError> {
+    if bytes.len() > MAX_IMPORT_BYTES {
+        return Err(ServerFnError::new("File too large (max 20 MB)".to_string()));
+    }
+    import_file(repo, &bytes, dry_run, None).await
+}
+
+/// A backup being restored (see `backup::restore`).
+pub(crate) struct Restore<'a> {
+    pub name: &'a str,
+    /// Replace the stored settings in the import's transaction. The kids are checked
+    /// against these limits.
+    pub settings: Settings,
+}
+
+/// `import_data` without the upload size limit, for backups read from the
+/// configured store, which may have grown past it.
+pub(crate) async fn import_file(
+    repo: &(impl KidsRepository + SettingsRepository + ImportRepository),
+    bytes: &[u8],
+    dry_run: bool,
+    restore: Option<Restore<'_>>,
+) -> Result<ImportPreview, ServerFnError> {
+    let document = match parse(bytes) {
+        Ok(document) => document,
+        Err(e) => {
+            return Ok(ImportPreview {
+                errors: vec![e],
+                ..ImportPreview::default()
+            })
+        }
+    };
+
+    let limits = match &restore {
+        Some(restore) => restore.settings.limits,
+        None => repo.settings().await?.limits,
+    };
+    let existing = repo
+        .list_active()
+        .await?
+        .into_iter()
+        .chain(repo.list_archived().await?)
+        .collect::<Vec<_>>();
+    let now = clock::now();
+
+    let mut errors = Vec::new();
+    if let Some(version) = document.format_version {
+        if version > EXPORT_FORMAT_VERSION {
+            errors.push(format!(
+                "The file is format version {version}; this version reads up to {EXPORT_FORMAT_VERSION}"
+            ));
+        }
+    }
+
+    let mut preview = ImportPreview::default();
+    let mut batch = ImportBatch::default();
+    let restored_from = restore.map(|restore| {
+        batch.settings = Some(restore.settings);
+        restore.name
+    });
+    // File kid id -> index into `batch.kids`
+    let mut kid_index: HashMap<u32, usize> = HashMap::new();
+    // Index into `batch.kids` -> hash of the kid's name, for the keys of keyless notes
+    let mut name_hashes: Vec<u64> = Vec::new();
+    let mut new_active: Vec<String> = Vec::new();
+    let mut seen_names: Vec<String> = Vec::new();
+    for (row, kid) in document.kids.into_iter().enumerate() {
+        let at = format!("Kid {} (id {})", row + 1, kid.id);
+        if kid_index.contains_key(&kid.id) {
+            errors.push(format!("{at}: id used by another kid in the file"));
+            continue;
+        }
+        let name = match validate_kid_name(&kid.name, &limits) {
+            Ok(name) => name,
+            Err(e) => {
+                errors.push(format!("{at}: {e}"));
+                continue;
+            }
+        };
+        if let Err(e) = ensure_unique_name(&name, seen_names.iter().map(String::as_str)) {
+            errors.push(format!("{at}: {e}"));
+            continue;
+        }
+        seen_names.push(name.clone());
+
+        if let Some(found) = existing.iter().find(|k| same_name(&k.name, &name)) {
+            preview.kids_matched.push(found.name.clone());
+            kid_index.insert(kid.id, batch.kids.len());
+            batch.kids.push(ImportedKid::Existing(found.id));
+            name_hashes.push(fnv1a(&name.to_lowercase()));
+            continue;
+        }
+        let (color, emoji) = match check_profile(kid.color, kid.emoji, kid.birthdate) {
+            Ok(profile) => profile,
+            Err(e) => {
+                errors.push(format!("{at}: {e}"));
+                continue;
+            }
+        };
+        if !kid.archived {
+            new_active.push(name.clone());
+        }
+        preview.kids_added.push(name.clone());
+        kid_index.insert(kid.id, batch.kids.len());
+        name_hashes.push(fnv1a(&name.to_lowercase()));
+        batch.kids.push(ImportedKid::New {
+            name,
+            color,
+            emoji,
+            birthdate: kid.birthdate,
+            archived: kid.archived,
+        });
+    }
+
+    // (kid, time) -> keyless notes seen so far, to tell identical ones apart
+    let mut keyless: HashMap<(usize, NaiveDateTime), u32> = HashMap::new();
+    for (row, note) in document.notes.into_iter().enumerate() {
+        let at = format!("Note {}", row + 1);
+        let Some(&kid) = kid_index.get(&note.kid_id) else {
+            errors.push(format!("{at}: unknown kid id {}", note.kid_id));
+            continue;
+        };
+        if note.quantity != 1 && note.quantity != -1 {
+            errors.push(format!("{at}: quantity must be 1 or -1, not {}", note.quantity));
+            continue;
+        }
+        if note.occurred_at > now {
+            errors.push(format!("{at}: {} is in the future", note.occurred_at));
+            continue;
+        }
+        let idempotency_key = note.idempotency_key.filter(|k| !k.is_empty());
+        if idempotency_key.as_ref().is_some_and(|k| k.len() > 64) {
+            errors.push(format!("{at}: idempotency key longer than 64 characters"));
+            continue;
+        }
+        // Notes without a key get one made from the kid's name and the time, so
+        // importing the same file again skips them like any other note
+        let idempotency_key = idempotency_key.or_else(|| {
+            let seen = keyless.entry((kid, note.occurred_at)).or_default();
+            *seen += 1;
+            Some(format!(
+                "import-{:016x}-{}-{seen}",
+                name_hashes[kid],
+                note.occurred_at.format("%Y%m%d%H%M%S%3f")
+            ))
+        });
+        batch.notes.push(ImportedNote {
+            kid,
+            quantity: note.quantity,
+            occurred_at: note.occurred_at,
+            created_at: note.created_at.unwrap_or(now),
+            idempotency_key,
+        });
+    }
+
+    if !errors.is_empty() {
+        if errors.len() > MAX_ERRORS {
+            let more = errors.len() - MAX_ERRORS;
+            errors.truncate(MAX_ERRORS);
+            errors.push(format!("…and {more} more"));
+        }
+        preview.errors = errors;
+        return Ok(preview);
+    }
+
+    let counts = repo
+        .import(
+            &batch,
+            !dry_run,
+            |active| {
+                for (i, name) in new_active.iter().enumerate() {
+                    ensure_room_for_kid((active.len() + i) as u32, &limits)?;
+                    ensure_unique_name(name, active.iter().map(String::as_str))?;
+                }
+                Ok(())
+            },
+            |counts| match restored_from {
+                Some(name) => audit::entry(
+                    AuditAction::BackupRestored,
+                    None,
+                    None,
+                    Some(name.to_string()),
+                ),
+                None => audit::entry(
+                    AuditAction::DataImported,
+                    None,
+                    None,
+                    Some(format!(
+                        "{} kids added, {} notes added, {} notes skipped",
+                        counts.kids_added, counts.notes_added, counts.notes_skipped
+                    )),
+                ),
+            },
+        )
+        .await?;
+    preview.notes_added = counts.notes_added;
+    preview.notes_skipped = counts.notes_skipped;
+    preview.committed = !dry_run;
+
+    if !dry_run {
+        publish(ChangeEvent::KidsChanged);
+    }
+    Ok(preview)
+}
+
+/// 64-bit FNV-1a. Unlike `DefaultHasher` it is the same in every build, which the
+/// keys of keyless notes need.
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
@@ -1,29 +1,30 @@
-use crate::models::{CountAggregation, GetKidsResponse, KidSummary};
-#[cfg(feature = "server")]
-use crate::models::{CountMetadata, Kid};
-#[cfg(feature = "server")]
+use crate::models::{CountAggregation, GetKidsResponse, Kid, KidLimits, KidSummary};
+use crate::models::{AuditAction, ChangeEvent, CountMetadata, MAX_BACKDATE_DAYS};
 use std::collections::HashMap;
-#[cfg(feature = "server")]
-use std::fmt;
-#[cfg(feature = "server")]
-use std::sync::{LazyLock, Mutex};
-
-#[cfg(feature = "server")]
-use crate::backend::turso::get_db;
-
-#[cfg(feature = "server")]
-use chrono::Datelike;
-
-#[cfg(feature = "server")]
-use libsql::de;
+use std::sync::LazyLock;
+
+use crate::backend::audit::{self, on_off};
+use crate::backend::avatars::avatar_storage;
+use crate::backend::clock;
+use crate::backend::events::publish;
+use crate::backend::repository::{
+    AuditRepository, AvatarsRepository, KidsRepository, NotesRepository, Settings,
+    SettingsRepository,
+};
+use crate::backend::stats::cycle_bounds;
+use crate::backend::validation::{
+    ensure_room_for_kid, ensure_unique_name, validate_kid_name, validate_kid_order,
+    validate_limits, validate_utc_offset, ValidationError,
+};
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, TimeDelta};
 
 use dioxus::prelude::*;
+use uuid::Uuid;
 
-#[cfg(feature = "server")]
-const ALLOWED_GRANULARITIES: &[&str] = &["DAILY", "WEEKLY", "MONTHLY", "YEARLY"];
+pub(crate) const ALLOWED_GRANULARITIES: &[&str] = &["DAILY", "WEEKLY", "MONTHLY", "YEARLY"];
 
-#[cfg(feature = "server")]
-const FORMAT_MAP: LazyLock<HashMap<&str, &str>> = LazyLock::new(|| {
+pub(crate) static FORMAT_MAP: LazyLock<HashMap<&str, &str>> = LazyLock::new(|| {
     HashMap::from([
         ("DAILY", "%Y-%m-%d"),
         ("WEEKLY", "%Y-W%W"),
@@ -32,338 +33,643 @@ const FORMAT_MAP: LazyLock<HashMap<&str, &str>> = LazyLock::new(|| {
     ])
 });
 
-#[server]
-pub async fn decrement_kid_count(kid_id: u32) -> Result<(), ServerFnError> {
-    // Here you would typically interact with your database to decrement the count for the specified kid.
-    // For demonstration purposes, we'll just print the kid_id.
-    log_note(kid_id, false).await
-}
-
-#[server]
-pub async fn increment_kid_count(kid_id: u32) -> Result<(), ServerFnError> {
-    // Here you would typically interact with your database to increment the count for the specified kid.
-    // For demonstration purposes, we'll just print the kid_id.
-    log_note(kid_id, true).await
+/// Logs a -1 note and returns the kid with its updated count. `idempotency_key` is
+/// generated by the client once per tap, so a retried request is recorded only once.
+/// `occurred_at` backdates the note (see `log_note`); it is the client's wall-clock
+/// time, `utc_offset_minutes` ahead of UTC.
+pub async fn decrement_kid_count(
+    repo: &(impl KidsRepository + NotesRepository + SettingsRepository + AuditRepository),
+    kid_id: u32,
+    idempotency_key: Option<String>,
+    occurred_at: Option<NaiveDateTime>,
+    utc_offset_minutes: i32,
+) -> Result<Kid, ServerFnError> {
+    let occurred_at = to_utc(occurred_at, utc_offset_minutes)?;
+    log_note(repo, kid_id, false, idempotency_key, occurred_at).await?;
+    let kid = get_current_kid(repo, kid_id).await?;
+    publish(ChangeEvent::KidCount(kid.clone()));
+    Ok(kid)
 }
 
-#[derive(Debug, serde::Deserialize)]
-#[allow(dead_code)]
-struct KidTotalRow {
-    period: String,
+/// Logs a +1 note and returns the updated kid. See `decrement_kid_count` for the options.
+pub async fn increment_kid_count(
+    repo: &(impl KidsRepository + NotesRepository + SettingsRepository + AuditRepository),
     kid_id: u32,
-    total: i32,
+    idempotency_key: Option<String>,
+    occurred_at: Option<NaiveDateTime>,
+    utc_offset_minutes: i32,
+) -> Result<Kid, ServerFnError> {
+    let occurred_at = to_utc(occurred_at, utc_offset_minutes)?;
+    log_note(repo, kid_id, true, idempotency_key, occurred_at).await?;
+    let kid = get_current_kid(repo, kid_id).await?;
+    publish(ChangeEvent::KidCount(kid.clone()));
+    Ok(kid)
 }
 
-#[cfg(feature = "server")]
-#[derive(Debug, serde::Deserialize)]
-#[allow(dead_code)]
-struct KidRow {
-    id: u32,
-    name: String,
-    created_at: String,
+/// A client's wall-clock time, `utc_offset_minutes` ahead of UTC, as stored: in UTC.
+fn to_utc(
+    at: Option<NaiveDateTime>,
+    utc_offset_minutes: i32,
+) -> Result<Option<NaiveDateTime>, ValidationError> {
+    validate_utc_offset(utc_offset_minutes)?;
+    Ok(at.map(|at| at - TimeDelta::minutes(utc_offset_minutes as i64)))
 }
 
-#[derive(Debug, serde::Deserialize)]
-#[cfg(feature = "server")]
-struct SettingsRow {
-    id: u32,
-    granularity: String,
-    created_at: String,
+/// Describes the cycle containing `day`.
+fn cycle_aggregation(settings: &Settings, day: NaiveDate) -> CountAggregation {
+    match settings.granularity.as_str() {
+        "DAILY" => CountAggregation::Daily(day.day(), day.month(), day.year() as u32),
+        "WEEKLY" => CountAggregation::Weekly(day.iso_week().week(), day.month(), day.year() as u32),
+        "MONTHLY" => CountAggregation::Monthly(day.month(), day.year() as u32),
+        "YEARLY" => CountAggregation::Yearly(day.year() as u32),
+        _ => CountAggregation::Monthly(day.month(), day.year() as u32), // default to monthly if unrecognized
+    }
 }
 
-#[derive(Debug, serde::Deserialize)]
-#[cfg(feature = "server")]
-struct SummaryRow {
-    period: Option<String>,
-    total: Option<i32>,
+/// Records a +1/-1 note for a kid. A note whose `idempotency_key` was already
+/// recorded is silently skipped. Notes without one get a random key, so every
+/// stored note can be matched when an export of it is imported again.
+///
+/// `occurred_at` backdates the note: it counts towards the cycle it happened in,
+/// while the time it was recorded is kept apart. See `check_occurred_at` for bounds.
+pub async fn log_note(
+    repo: &(impl KidsRepository + NotesRepository + SettingsRepository + AuditRepository),
     kid_id: u32,
-    name: String,
-    created_at: String,
-    latest_note: Option<String>,
-}
-
-#[cfg(feature = "server")]
-impl SummaryRow {
-    fn to_kid(&self) -> Kid {
-        Kid {
-            id: self.kid_id,
-            name: self.name.clone(),
-            count: self.total.unwrap_or(0) as i8,
-            latest_note: self
-                .latest_note
-                .as_deref()
-                .and_then(|s| chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").ok())
-        }
+    add: bool,
+    idempotency_key: Option<String>,
+    occurred_at: Option<NaiveDateTime>,
+) -> Result<(), ServerFnError> {
+    if idempotency_key.as_ref().is_some_and(|k| k.is_empty() || k.len() > 64) {
+        return Err(ServerFnError::new(
+            "Idempotency key must be 1 to 64 characters".to_string(),
+        ));
     }
-}
-
-#[cfg(feature = "server")]
-fn get_current_cycle(settings: &SettingsRow) -> CountAggregation {
-    let now = chrono::offset::Utc::now().naive_utc();
 
-    match settings.granularity.as_str() {
-        "DAILY" => CountAggregation::Daily(now.day(), now.month(), now.year() as u32),
-        "WEEKLY" => CountAggregation::Weekly(now.iso_week().week(), now.month(), now.year() as u32),
-        "MONTHLY" => CountAggregation::Monthly(now.month(), now.year() as u32),
-        _ => CountAggregation::Monthly(now.month(), now.year() as u32), // default to monthly if unrecognized
-    }
-}
+    let idempotency_key =
+        idempotency_key.unwrap_or_else(|| format!("note-{}", Uuid::new_v4().simple()));
 
-#[cfg(feature = "server")]
-async fn get_count_metadata() -> Result<SettingsRow, ServerFnError> {
-    let conn = get_db().await;
+    let now = clock::now();
+    let backdated = occurred_at.is_some();
+    let occurred_at = match occurred_at {
+        Some(occurred_at) => {
+            check_occurred_at(&repo.settings().await?, occurred_at, now)?;
+            // Slightly ahead means the device's clock is; the note happened just now
+            occurred_at.min(now)
+        }
+        None => now,
+    };
 
-    let mut rows = conn
-        .query(
-            "SELECT id, granularity, created_at FROM settings LIMIT 1",
-            (),
-        )
-        .await
-        .map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    if let Some(row) = rows
-        .next()
-        .await
-        .map_err(|e| ServerFnError::new(e.to_string()))?
-    {
-        let settings_row =
-            de::from_row::<SettingsRow>(&row).map_err(|e| ServerFnError::new(e.to_string()))?;
-
-        Ok(settings_row)
+    // Archived kids keep their history but take no new notes
+    let kid = repo
+        .list_active()
+        .await?
+        .into_iter()
+        .find(|k| k.id == kid_id)
+        .ok_or_else(|| ServerFnError::new("Kid not found or archived".to_string()))?;
+    let quantity = if add { 1 } else { -1 };
+    let after = if backdated {
+        format!("{quantity:+} (happened {})", occurred_at.format("%Y-%m-%d %H:%M"))
     } else {
-        Err(ServerFnError::new("No settings found".to_string()))
+        format!("{quantity:+}")
+    };
+    // Retries are skipped by the repository, entry included, so they don't show up twice
+    repo.record(
+        kid_id,
+        quantity,
+        Some(&idempotency_key),
+        occurred_at,
+        audit::entry(AuditAction::NoteLogged, Some((kid.id, &kid.name)), None, Some(after)),
+    )
+    .await?;
+    Ok(())
+}
+
+/// How far ahead of the server a device's clock may be. Taps queued offline carry
+/// the device's time.
+const MAX_CLOCK_SKEW: TimeDelta = TimeDelta::minutes(5);
+
+/// A backdated note must be at most `MAX_BACKDATE_DAYS` old, not in the future
+/// (give or take `MAX_CLOCK_SKEW`), and in the current cycle unless the family
+/// allows backdating into closed cycles.
+fn check_occurred_at(
+    settings: &Settings,
+    occurred_at: NaiveDateTime,
+    now: NaiveDateTime,
+) -> Result<(), ServerFnError> {
+    if occurred_at > now + MAX_CLOCK_SKEW {
+        return Err(ServerFnError::new(
+            "A note cannot be dated in the future".to_string(),
+        ));
+    }
+    if now - occurred_at > TimeDelta::days(MAX_BACKDATE_DAYS) {
+        return Err(ServerFnError::new(format!(
+            "Notes can be backdated by at most {MAX_BACKDATE_DAYS} days"
+        )));
+    }
+    let (cycle_start, _) = cycle_bounds(&settings.granularity, now.date())?;
+    if occurred_at < cycle_start && !settings.backdate_closed_cycles {
+        return Err(ServerFnError::new(
+            "That cycle is closed; backdating into closed cycles is turned off".to_string(),
+        ));
     }
+    Ok(())
 }
 
-/// Fetches the current granularity setting as a string (DAILY, WEEKLY, MONTHLY, YEARLY).
-#[cfg(feature = "server")]
-pub async fn log_note(kid_id: u32, add: bool) -> Result<(), ServerFnError> {
-    let conn = get_db().await;
-    let quantity = if add { 1 } else { -1 };
+/// Returns whether notes may be backdated into closed cycles.
+pub async fn get_backdate_closed_cycles(
+    repo: &impl SettingsRepository,
+) -> Result<bool, ServerFnError> {
+    Ok(repo.settings().await?.backdate_closed_cycles)
+}
 
-    conn.execute(
-        "INSERT INTO notes (kid_id, quantity) VALUES (?1, ?2)",
-        libsql::params![kid_id, quantity],
+/// Allows or forbids backdating notes into closed cycles.
+pub async fn update_backdate_closed_cycles(
+    repo: &(impl SettingsRepository + AuditRepository),
+    enabled: bool,
+) -> Result<(), ServerFnError> {
+    let before = repo.settings().await?.backdate_closed_cycles;
+    repo.set_backdate_closed_cycles(
+        enabled,
+        audit::entry(
+            AuditAction::BackdatingChanged,
+            None,
+            Some(on_off(before)),
+            Some(on_off(enabled)),
+        ),
     )
-    .await
-    .map_err(|e| ServerFnError::new(e.to_string()))?;
-
+    .await?;
+    publish(ChangeEvent::SettingsChanged);
     Ok(())
 }
 
 /// Fetches the current granularity setting as a string (DAILY, WEEKLY, MONTHLY, YEARLY).
-#[server]
-pub async fn get_granularity() -> Result<String, ServerFnError> {
-    let settings = get_count_metadata().await?;
+pub async fn get_granularity(repo: &impl SettingsRepository) -> Result<String, ServerFnError> {
+    let settings = repo.settings().await?;
     Ok(settings.granularity)
 }
 
-/// Updates the granularity setting in the database.
+/// Updates the granularity setting.
 /// Accepts: "DAILY", "WEEKLY", "MONTHLY", "YEARLY".
-#[server]
-pub async fn update_granularity(granularity: String) -> Result<(), ServerFnError> {
+pub async fn update_granularity(
+    repo: &(impl SettingsRepository + AuditRepository),
+    granularity: String,
+) -> Result<(), ServerFnError> {
     if !ALLOWED_GRANULARITIES.contains(&granularity.as_str()) {
         return Err(ServerFnError::new(format!(
             "Invalid granularity: '{granularity}'. Must be one of: {ALLOWED_GRANULARITIES:?}"
         )));
     }
-    let conn = get_db().await;
-    conn.execute(
-        "UPDATE settings SET granularity = ?1 WHERE id = 1",
-        libsql::params![granularity],
+    let before = repo.settings().await?.granularity;
+    repo.set_granularity(
+        &granularity,
+        audit::entry(
+            AuditAction::GranularityChanged,
+            None,
+            Some(before),
+            Some(granularity.clone()),
+        ),
     )
-    .await
-    .map_err(|e| ServerFnError::new(e.to_string()))?;
+    .await?;
+    publish(ChangeEvent::SettingsChanged);
     Ok(())
 }
 
-/// Fetches the list of kids along with their count metadata.
-/// Intended to be used at the home screen
-#[server]
-pub async fn get_kids() -> Result<GetKidsResponse, ServerFnError> {
-    let conn = get_db().await;
-    let now = chrono::offset::Utc::now().naive_utc();
-    let meta_raw = get_count_metadata().await?;
-
-    let (grain_format, grain_value) = match meta_raw.granularity.as_str() {
-        "DAILY" => ("%Y-%m-%d", now.format("%Y-%m-%d").to_string()),
-        "WEEKLY" => {
-            let week_start =
-                now - chrono::Duration::days(now.weekday().num_days_from_monday() as i64);
-            ("%Y-W%W", week_start.format("%Y-%m-%d 00:00:00").to_string())
-        }
-        "MONTHLY" => ("%Y-%m", now.format("%Y-%m").to_string()),
-        "YEARLY" => ("%Y", now.format("%Y").to_string()),
-        _ => {
-            return Err(ServerFnError::new(
-                "Invalid granularity in settings".to_string(),
-            ))
-        }
-    };
+/// Returns whether the opt-in leaderboard mode is enabled.
+pub async fn get_leaderboard(repo: &impl SettingsRepository) -> Result<bool, ServerFnError> {
+    let settings = repo.settings().await?;
+    Ok(settings.leaderboard)
+}
 
-    let query = format!(
-        "
-    SELECT
-        strftime('{}', notes.created_at) AS period,
-        SUM(quantity) AS total,
-        kids.id AS kid_id,
-        kids.name as name,
-        kids.created_at AS created_at,
-        MAX(notes.created_at) AS latest_note
-    FROM kids
-    LEFT JOIN notes ON notes.kid_id = kids.id AND notes.created_at >= :grain_value
-    GROUP BY kid_id, period",
-        grain_format
-    );
+/// Enables or disables the leaderboard mode (kids ranked by score on the home screen).
+pub async fn update_leaderboard(
+    repo: &(impl SettingsRepository + AuditRepository),
+    enabled: bool,
+) -> Result<(), ServerFnError> {
+    let before = repo.settings().await?.leaderboard;
+    repo.set_leaderboard(
+        enabled,
+        audit::entry(
+            AuditAction::LeaderboardChanged,
+            None,
+            Some(on_off(before)),
+            Some(on_off(enabled)),
+        ),
+    )
+    .await?;
+    publish(ChangeEvent::SettingsChanged);
+    Ok(())
+}
+
+/// Debug builds only: the backend's current time (UTC), which may be time travelling.
+#[cfg(debug_assertions)]
+pub async fn get_backend_time() -> Result<chrono::NaiveDateTime, ServerFnError> {
+    Ok(clock::now())
+}
+
+/// Debug builds only: moves the backend clock to `target`, or back to the real time
+/// with `None`, and refreshes open apps. Returns the new backend time.
+#[cfg(debug_assertions)]
+pub async fn travel_to(
+    repo: &impl AuditRepository,
+    target: Option<chrono::NaiveDateTime>,
+) -> Result<chrono::NaiveDateTime, ServerFnError> {
+    let before = clock::now();
+    let now = clock::travel_to(target);
+    // Stamped with the new time, so the entry also shows where the clock landed
+    let format = |time: NaiveDateTime| time.format("%Y-%m-%d %H:%M:%S").to_string();
+    audit::record(
+        repo,
+        AuditAction::TimeTravelled,
+        None,
+        Some(format(before)),
+        Some(format(now)),
+    )
+    .await?;
+    publish(ChangeEvent::SettingsChanged);
+    Ok(now)
+}
 
-    let stm = conn
-        .prepare(&query)
-        .await
-        .map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    let mut rows = stm
-        .query(libsql::named_params! { ":grain_value": grain_value })
-        .await
-        .map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    let mut kids = Vec::new();
-
-    while let Some(row) = rows
-        .next()
-        .await
-        .map_err(|e| ServerFnError::new(e.to_string()))?
-    {
-        let kid_row =
-            de::from_row::<SummaryRow>(&row).map_err(|e| ServerFnError::new(e.to_string()))?;
-        kids.push(kid_row.to_kid());
+/// Fetches the list of kids along with their count metadata.
+/// Intended to be used at the home screen. `period` is any day of the cycle to show,
+/// the current one when `None`; cycles that haven't started yet are rejected.
+pub async fn get_kids(
+    repo: &(impl KidsRepository + NotesRepository + SettingsRepository),
+    period: Option<NaiveDate>,
+) -> Result<GetKidsResponse, ServerFnError> {
+    let settings = repo.settings().await?;
+    let today = clock::today();
+    let day = period.unwrap_or(today);
+    let (start, end) = cycle_bounds(&settings.granularity, day)?;
+    if start.date() > today {
+        return Err(ServerFnError::new(
+            "Cannot show a cycle that hasn't started yet".to_string(),
+        ));
     }
 
-    let aggregation = get_current_cycle(&meta_raw);
+    let kids = kids_in_cycle(repo, &settings, day).await?;
+
+    let aggregation = cycle_aggregation(&settings, day);
 
     let response = GetKidsResponse {
         kids,
         count_metadata: CountMetadata {
             aggregation: aggregation,
+            leaderboard: settings.leaderboard,
+            period_start: start.date(),
+            period_end: end.date(),
+            current: today < end.date(),
+            today,
         },
     };
     Ok(response)
 }
 
-/// Fetches just the list of kids (id + name) without count metadata.
-/// Intended for the settings/management screen.
-#[server]
-pub async fn list_kids() -> Result<Vec<KidSummary>, ServerFnError> {
-    let conn = get_db().await;
-    let mut rows = conn
-        .query("SELECT id, name FROM kids ORDER BY name ASC", ())
-        .await
-        .map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    let mut kids = Vec::new();
-    while let Some(row) = rows
-        .next()
-        .await
-        .map_err(|e| ServerFnError::new(e.to_string()))?
-    {
-        let kid =
-            de::from_row::<KidSummary>(&row).map_err(|e| ServerFnError::new(e.to_string()))?;
-        kids.push(kid);
-    }
-    Ok(kids)
+/// Fetches a single active kid with its count for the current cycle.
+async fn get_current_kid(
+    repo: &(impl KidsRepository + NotesRepository + SettingsRepository),
+    kid_id: u32,
+) -> Result<Kid, ServerFnError> {
+    let settings = repo.settings().await?;
+    kids_in_cycle(repo, &settings, clock::today())
+        .await?
+        .into_iter()
+        .find(|k| k.id == kid_id)
+        .ok_or_else(|| ServerFnError::new("Kid not found".to_string()))
 }
 
-/// Adds a new kid. Enforces a maximum of 10 kids.
-#[server]
-pub async fn add_kid(name: String) -> Result<KidSummary, ServerFnError> {
-    let name = name.trim().to_string();
-    if name.is_empty() {
-        return Err(ServerFnError::new("Name cannot be empty".to_string()));
-    }
-    if name.len() > 50 {
+/// Loads active kids, in display order, with their totals for the cycle containing `day`.
+async fn kids_in_cycle(
+    repo: &(impl KidsRepository + NotesRepository),
+    settings: &Settings,
+    day: NaiveDate,
+) -> Result<Vec<Kid>, ServerFnError> {
+    if !ALLOWED_GRANULARITIES.contains(&settings.granularity.as_str()) {
         return Err(ServerFnError::new(
-            "Name too long (max 50 characters)".to_string(),
+            "Invalid granularity in settings".to_string(),
         ));
     }
+    let (start, end) = cycle_bounds(&settings.granularity, day)?;
+
+    let totals = repo.totals_between(start, end).await?;
+    let kids = repo
+        .list_active()
+        .await?
+        .into_iter()
+        .map(|summary| {
+            let totals = totals.get(&summary.id).cloned().unwrap_or_default();
+            Kid {
+                id: summary.id,
+                name: summary.name,
+                count: totals.total,
+                latest_note: totals.latest_note,
+                color: summary.color,
+                emoji: summary.emoji,
+                birthdate: summary.birthdate,
+                avatar_version: summary.avatar_version,
+            }
+        })
+        .collect();
+    Ok(kids)
+}
 
-    let conn = get_db().await;
-
-    // Enforce 10-kid limit
-    let mut count_rows = conn
-        .query("SELECT COUNT(*) as cnt FROM kids", ())
-        .await
-        .map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    if let Some(row) = count_rows
-        .next()
-        .await
-        .map_err(|e| ServerFnError::new(e.to_string()))?
-    {
-        let count: u32 = row.get(0).map_err(|e| ServerFnError::new(e.to_string()))?;
-        if count >= 10 {
-            return Err(ServerFnError::new("Maximum of 10 kids allowed".to_string()));
-        }
+/// Fetches just the list of active kids (id, name and profile) without count metadata.
+/// Intended for the settings/management screen.
+pub async fn list_kids(repo: &impl KidsRepository) -> Result<Vec<KidSummary>, ServerFnError> {
+    repo.list_active().await
+}
+
+/// Looks a kid up among the active kids, then the archived ones.
+pub(crate) async fn find_kid(
+    repo: &impl KidsRepository,
+    kid_id: u32,
+) -> Result<Option<KidSummary>, ServerFnError> {
+    if let Some(kid) = repo.list_active().await?.into_iter().find(|k| k.id == kid_id) {
+        return Ok(Some(kid));
     }
+    Ok(repo
+        .list_archived()
+        .await?
+        .into_iter()
+        .find(|k| k.id == kid_id))
+}
 
-    conn.execute(
-        "INSERT INTO kids (name, created_at) VALUES (?1, datetime('now', 'utc'))",
-        libsql::params![name.clone()],
-    )
-    .await
-    .map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    // Retrieve the inserted kid
-    let mut rows = conn
-        .query(
-            "SELECT id, name FROM kids WHERE name = ?1 ORDER BY id DESC LIMIT 1",
-            libsql::params![name],
+/// Fetches archived kids, most recently archived first.
+pub async fn list_archived_kids(
+    repo: &impl KidsRepository,
+) -> Result<Vec<KidSummary>, ServerFnError> {
+    repo.list_archived().await
+}
+
+/// Adds a new kid, enforcing the family's kid limit, name rules and unique names.
+/// The repository runs the checks in the same write as the insert, so concurrent
+/// requests cannot go past the limit.
+pub async fn add_kid(
+    repo: &(impl KidsRepository + SettingsRepository + AuditRepository),
+    name: String,
+) -> Result<KidSummary, ServerFnError> {
+    let limits = repo.settings().await?.limits;
+    let name = validate_kid_name(&name, &limits)?;
+
+    let kid = repo
+        .add(
+            &name,
+            |existing| ensure_can_add_kid(&name, existing, &limits),
+            |kid| {
+                audit::entry(
+                    AuditAction::KidAdded,
+                    Some((kid.id, &kid.name)),
+                    None,
+                    Some(kid.name.clone()),
+                )
+            },
         )
-        .await
-        .map_err(|e| ServerFnError::new(e.to_string()))?;
-
-    if let Some(row) = rows
-        .next()
-        .await
-        .map_err(|e| ServerFnError::new(e.to_string()))?
-    {
-        let kid =
-            de::from_row::<KidSummary>(&row).map_err(|e| ServerFnError::new(e.to_string()))?;
-        Ok(kid)
-    } else {
-        Err(ServerFnError::new(
-            "Failed to retrieve inserted kid".to_string(),
-        ))
-    }
+        .await?;
+    publish(ChangeEvent::KidsChanged);
+    Ok(kid)
+}
+
+/// Fails when one more active kid named `name` would break the family's limits.
+/// `existing` holds the active kids' names; archived kids don't count.
+fn ensure_can_add_kid(
+    name: &str,
+    existing: &[String],
+    limits: &KidLimits,
+) -> Result<(), ValidationError> {
+    ensure_room_for_kid(existing.len() as u32, limits)?;
+    ensure_unique_name(name, existing.iter().map(String::as_str))
+}
+
+/// Returns the family's kid limits.
+pub async fn get_kid_limits(repo: &impl SettingsRepository) -> Result<KidLimits, ServerFnError> {
+    Ok(repo.settings().await?.limits)
+}
+
+/// Updates the family's kid limits. Existing kids above a lowered limit are kept.
+pub async fn update_kid_limits(
+    repo: &(impl SettingsRepository + AuditRepository),
+    limits: KidLimits,
+) -> Result<(), ServerFnError> {
+    validate_limits(&limits)?;
+    let before = repo.settings().await?.limits;
+    let format = |limits: KidLimits| {
+        format!(
+            "{} kids, names up to {} characters",
+            limits.max_kids, limits.max_name_length
+        )
+    };
+    repo.set_limits(
+        limits,
+        audit::entry(
+            AuditAction::LimitsChanged,
+            None,
+            Some(format(before)),
+            Some(format(limits)),
+        ),
+    )
+    .await?;
+    publish(ChangeEvent::SettingsChanged);
+    Ok(())
+}
+
+/// Archives a kid: hidden from the home screen and stats, but history is kept
+/// and the kid can be restored.
+pub async fn archive_kid(
+    repo: &(impl KidsRepository + AuditRepository),
+    kid_id: u32,
+) -> Result<(), ServerFnError> {
+    let kid = find_kid(repo, kid_id)
+        .await?
+        .ok_or_else(|| ServerFnError::new("Kid not found".to_string()))?;
+    repo.archive(
+        kid_id,
+        audit::entry(AuditAction::KidArchived, Some((kid.id, &kid.name)), None, None),
+    )
+    .await?;
+    publish(ChangeEvent::KidsChanged);
+    Ok(())
+}
+
+/// Restores an archived kid, placing it last. Subject to the kid limit and unique names.
+pub async fn restore_kid(
+    repo: &(impl KidsRepository + SettingsRepository + AuditRepository),
+    kid_id: u32,
+) -> Result<(), ServerFnError> {
+    let limits = repo.settings().await?.limits;
+    let kid = find_kid(repo, kid_id)
+        .await?
+        .ok_or_else(|| ServerFnError::new("Kid not found".to_string()))?;
+    repo.restore(
+        kid_id,
+        |name, existing| ensure_can_add_kid(name, existing, &limits),
+        audit::entry(AuditAction::KidRestored, Some((kid.id, &kid.name)), None, None),
+    )
+    .await?;
+    publish(ChangeEvent::KidsChanged);
+    Ok(())
+}
+
+/// Permanently deletes an archived kid together with their notes and photo.
+/// Active kids must be archived first.
+pub async fn delete_kid(
+    repo: &(impl KidsRepository + AvatarsRepository + AuditRepository),
+    kid_id: u32,
+) -> Result<(), ServerFnError> {
+    let kid = find_kid(repo, kid_id)
+        .await?
+        .ok_or_else(|| ServerFnError::new("Kid not found".to_string()))?;
+    repo.delete(
+        kid_id,
+        |archived| {
+            if archived {
+                Ok(())
+            } else {
+                Err(ServerFnError::new(
+                    "Archive the kid before deleting permanently".to_string(),
+                ))
+            }
+        },
+        // The entry outlives the kid, keeping the name they had
+        audit::entry(AuditAction::KidDeleted, Some((kid.id, &kid.name)), None, None),
+    )
+    .await?;
+
+    // Photos stored as blobs went with the kid; ones on disk live outside the repository
+    avatar_storage().delete_file(kid_id).await?;
+    publish(ChangeEvent::KidsChanged);
+    Ok(())
+}
+
+/// Renames a kid, applying the same name rules as `add_kid`.
+pub async fn rename_kid(
+    repo: &(impl KidsRepository + SettingsRepository + AuditRepository),
+    kid_id: u32,
+    new_name: String,
+) -> Result<(), ServerFnError> {
+    let limits = repo.settings().await?.limits;
+    let new_name = validate_kid_name(&new_name, &limits)?;
+
+    let before = find_kid(repo, kid_id).await?.map(|k| k.name);
+    repo.rename(
+        kid_id,
+        &new_name,
+        |others| ensure_unique_name(&new_name, others.iter().map(String::as_str)),
+        audit::entry(
+            AuditAction::KidRenamed,
+            Some((kid_id, &new_name)),
+            before,
+            Some(new_name.clone()),
+        ),
+    )
+    .await?;
+    publish(ChangeEvent::KidsChanged);
+    Ok(())
 }
 
-/// Deletes a kid by id.
-#[server]
-pub async fn delete_kid(kid_id: u32) -> Result<(), ServerFnError> {
-    let conn = get_db().await;
-    conn.execute("DELETE FROM kids WHERE id = ?1", libsql::params![kid_id])
-        .await
-        .map_err(|e| ServerFnError::new(e.to_string()))?;
+/// Stores a new display order for the kids. `kid_ids` lists every active kid exactly
+/// once, first one on top; any other list is rejected.
+pub async fn reorder_kids(
+    repo: &(impl KidsRepository + AuditRepository),
+    kid_ids: Vec<u32>,
+) -> Result<(), ServerFnError> {
+    let kids = repo.list_active().await?;
+    let name = |kid_id: &u32| {
+        kids.iter()
+            .find(|k| k.id == *kid_id)
+            .map(|k| k.name.as_str())
+    };
+    let before = kids
+        .iter()
+        .map(|k| k.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let after = kid_ids
+        .iter()
+        .filter_map(name)
+        .collect::<Vec<_>>()
+        .join(", ");
+    repo.reorder(
+        &kid_ids,
+        // Checked again inside the write, against the kids active at that moment
+        |active_ids| validate_kid_order(&kid_ids, active_ids),
+        audit::entry(AuditAction::KidsReordered, None, Some(before), Some(after)),
+    )
+    .await?;
+    publish(ChangeEvent::KidsChanged);
+    Ok(())
+}
+
+/// Updates a kid's profile details. `color` must be a `#rrggbb` hex value and `emoji`
+/// at most 8 characters; `None` clears the field.
+pub async fn update_kid_profile(
+    repo: &(impl KidsRepository + AuditRepository),
+    kid_id: u32,
+    color: Option<String>,
+    emoji: Option<String>,
+    birthdate: Option<NaiveDate>,
+) -> Result<(), ServerFnError> {
+    let (color, emoji) = check_profile(color, emoji, birthdate)?;
+
+    let before = find_kid(repo, kid_id)
+        .await?
+        .ok_or_else(|| ServerFnError::new("Kid not found".to_string()))?;
+    let entry = audit::entry(
+        AuditAction::KidProfileUpdated,
+        Some((kid_id, &before.name)),
+        Some(describe_profile(&before.color, &before.emoji, before.birthdate)),
+        Some(describe_profile(&color, &emoji, birthdate)),
+    );
+    repo.update_profile(kid_id, color, emoji, birthdate, entry).await?;
+    publish(ChangeEvent::KidsChanged);
     Ok(())
 }
 
-/// Renames a kid.
-#[server]
-pub async fn rename_kid(kid_id: u32, new_name: String) -> Result<(), ServerFnError> {
-    let new_name = new_name.trim().to_string();
-    if new_name.is_empty() {
-        return Err(ServerFnError::new("Name cannot be empty".to_string()));
+/// Validates profile details, returning the color and emoji trimmed, with blanks as
+/// `None`. `color` must be a `#rrggbb` hex value and `emoji` at most 8 characters.
+pub(crate) fn check_profile(
+    color: Option<String>,
+    emoji: Option<String>,
+    birthdate: Option<NaiveDate>,
+) -> Result<(Option<String>, Option<String>), ServerFnError> {
+    let color = color.map(|c| c.trim().to_lowercase()).filter(|c| !c.is_empty());
+    if let Some(color) = &color {
+        let valid = color.len() == 7
+            && color.starts_with('#')
+            && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+        if !valid {
+            return Err(ServerFnError::new(format!(
+                "Invalid color: '{color}'. Expected #rrggbb"
+            )));
+        }
     }
-    if new_name.len() > 50 {
+
+    let emoji = emoji.map(|e| e.trim().to_string()).filter(|e| !e.is_empty());
+    if emoji.as_ref().is_some_and(|e| e.chars().count() > 8) {
         return Err(ServerFnError::new(
-            "Name too long (max 50 characters)".to_string(),
+            "Avatar too long (max 8 characters)".to_string(),
         ));
     }
 
-    let conn = get_db().await;
-    conn.execute(
-        "UPDATE kids SET name = ?1 WHERE id = ?2",
-        libsql::params![new_name, kid_id],
+    if let Some(birthdate) = birthdate {
+        if birthdate > clock::today() {
+            return Err(ServerFnError::new(
+                "Birthdate cannot be in the future".to_string(),
+            ));
+        }
+    }
+
+    Ok((color, emoji))
+}
+
+/// Profile details as shown in the audit log.
+fn describe_profile(
+    color: &Option<String>,
+    emoji: &Option<String>,
+    birthdate: Option<NaiveDate>,
+) -> String {
+    format!(
+        "color {}, avatar {}, born {}",
+        color.as_deref().unwrap_or("none"),
+        emoji.as_deref().unwrap_or("none"),
+        birthdate.map_or("unknown".to_string(), |d| d.to_string())
     )
-    .await
-    .map_err(|e| ServerFnError::new(e.to_string()))?;
-    Ok(())
 }
@@ -0,0 +1,144 @@
+use libsql::{Connection, TransactionBehavior};
+
+/// Ordered schema migrations. Each entry is applied once and recorded in
+/// `schema_migrations`; append new entries, never edit applied ones.
+const MIGRATIONS: &[(u32, &str)] = &[
+    (
+        1,
+        "
+    CREATE TABLE IF NOT EXISTS kids (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL,
+        created_at TEXT NOT NULL DEFAULT (datetime('now', 'utc'))
+    );
+    CREATE TABLE IF NOT EXISTS notes (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        kid_id INTEGER NOT NULL REFERENCES kids(id),
+        quantity INTEGER NOT NULL,
+        created_at TEXT NOT NULL DEFAULT (datetime('now', 'utc'))
+    );
+    CREATE TABLE IF NOT EXISTS settings (
+        id INTEGER PRIMARY KEY,
+        granularity TEXT NOT NULL DEFAULT 'MONTHLY',
+        created_at TEXT NOT NULL DEFAULT (datetime('now', 'utc'))
+    );
+    INSERT OR IGNORE INTO settings (id, granularity) VALUES (1, 'MONTHLY');",
+    ),
+    (
+        2,
+        "ALTER TABLE settings ADD COLUMN leaderboard INTEGER NOT NULL DEFAULT 0;",
+    ),
+    (
+        3,
+        "
+    ALTER TABLE kids ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE kids ADD COLUMN color TEXT;
+    ALTER TABLE kids ADD COLUMN emoji TEXT;
+    ALTER TABLE kids ADD COLUMN birthdate TEXT;
+    UPDATE kids SET position = id;",
+    ),
+    (
+        4,
+        "
+    CREATE TABLE kid_avatars (
+        kid_id INTEGER PRIMARY KEY REFERENCES kids(id),
+        image BLOB NOT NULL,
+        updated_at TEXT NOT NULL DEFAULT (datetime('now', 'utc'))
+    );
+    ALTER TABLE kids ADD COLUMN avatar_version INTEGER;",
+    ),
+    (5, "ALTER TABLE kids ADD COLUMN archived_at TEXT;"),
+    (
+        6,
+        "
+    ALTER TABLE settings ADD COLUMN max_kids INTEGER NOT NULL DEFAULT 10;
+    ALTER TABLE settings ADD COLUMN max_name_length INTEGER NOT NULL DEFAULT 50;",
+    ),
+    (
+        7,
+        "
+    ALTER TABLE notes ADD COLUMN idempotency_key TEXT;
+    CREATE UNIQUE INDEX notes_idempotency_key ON notes(idempotency_key);",
+    ),
+    (
+        8,
+        "
+    ALTER TABLE notes ADD COLUMN occurred_at TEXT;
+    UPDATE notes SET occurred_at = created_at;
+    CREATE INDEX notes_occurred_at ON notes(occurred_at);
+    ALTER TABLE settings ADD COLUMN backdate_closed_cycles INTEGER NOT NULL DEFAULT 0;",
+    ),
+    (
+        9,
+        "
+    CREATE TABLE audit_log (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        actor TEXT NOT NULL,
+        action TEXT NOT NULL,
+        kid_id INTEGER,
+        kid_name TEXT,
+        before TEXT,
+        after TEXT,
+        created_at TEXT NOT NULL
+    );
+    CREATE INDEX audit_log_created_at ON audit_log(created_at);
+    CREATE TRIGGER audit_log_no_update BEFORE UPDATE ON audit_log
+    BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END;
+    CREATE TRIGGER audit_log_no_delete BEFORE DELETE ON audit_log
+    BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END;",
+    ),
+    // Every note gets a key, so an export imported back into its own database
+    // matches the notes already there
+    (
+        10,
+        "
+    UPDATE notes SET idempotency_key = 'note-' || lower(hex(randomblob(16)))
+    WHERE idempotency_key IS NULL;",
+    ),
+];
+
+/// Applies every migration newer than the latest one recorded in the database.
+pub async fn run_migrations(conn: &Connection) -> Result<(), libsql::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            applied_at TEXT NOT NULL DEFAULT (datetime('now', 'utc'))
+        )",
+        (),
+    )
+    .await?;
+
+    let current = schema_version(conn).await?;
+
+    for (version, sql) in MIGRATIONS.iter().filter(|(v, _)| *v > current) {
+        // A migration and its version row land together or not at all, so a failure
+        // halfway never leaves a schema that the next start would migrate again.
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .await?;
+        // Another instance may have applied it while this one waited for the lock
+        if schema_version(&tx).await? >= *version {
+            tx.rollback().await?;
+            continue;
+        }
+        tx.execute_batch(sql).await?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version) VALUES (?1)",
+            libsql::params![*version],
+        )
+        .await?;
+        tx.commit().await?;
+    }
+    Ok(())
+}
+
+/// The latest migration applied to the database, 0 for a new one.
+pub async fn schema_version(conn: &Connection) -> Result<u32, libsql::Error> {
+    let mut rows = conn
+        .query("SELECT COALESCE(MAX(version), 0) FROM schema_migrations", ())
+        .await?;
+    match rows.next().await? {
+        Some(row) => row.get(0),
+        None => Ok(0),
+    }
+}
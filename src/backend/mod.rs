@@ -1,2 +1,31 @@
+pub mod api;
+#[cfg(any(feature = "server", feature = "local"))]
+pub mod audit;
+pub mod avatars;
+#[cfg(any(feature = "server", feature = "local"))]
+pub mod backup;
+#[cfg(any(feature = "server", feature = "local"))]
+pub mod clock;
+pub mod events;
+#[cfg(any(feature = "server", feature = "local"))]
+pub mod export;
+#[cfg(any(feature = "server", feature = "local"))]
+pub mod import;
+#[cfg(any(feature = "server", feature = "local"))]
 pub mod kids;
+#[cfg(any(feature = "server", feature = "local"))]
+pub mod migrations;
+pub mod pwa;
+#[cfg(any(feature = "server", feature = "local"))]
+pub mod report;
+#[cfg(any(feature = "server", feature = "local"))]
+pub mod repository;
+#[cfg(any(feature = "server", feature = "local"))]
+pub mod seed;
+#[cfg(any(feature = "server", feature = "local"))]
+pub mod stats;
+#[cfg(any(feature = "server", feature = "local"))]
 pub mod turso;
+pub mod validation;
+#[cfg(all(test, any(feature = "server", feature = "local")))]
+mod tests;
@@ -0,0 +1,49 @@
+#[cfg(feature = "server")]
+use axum::{http::header, response::IntoResponse, routing::get, Router};
+
+#[cfg(feature = "server")]
+const SERVICE_WORKER: &str = include_str!("../../assets/sw.js");
+#[cfg(feature = "server")]
+const MANIFEST: &str = include_str!("../../assets/manifest.webmanifest");
+#[cfg(feature = "server")]
+const ICON: &[u8] = include_bytes!("../../assets/hotdog.svg");
+
+/// Routes for the installable web app. The service worker must be served from the
+/// site root (not the hashed asset folder) so its scope covers every page.
+#[cfg(feature = "server")]
+pub fn routes() -> Router {
+    Router::new()
+        .route("/sw.js", get(service_worker))
+        .route("/manifest.webmanifest", get(manifest))
+        .route("/icon.svg", get(icon))
+}
+
+#[cfg(feature = "server")]
+async fn service_worker() -> impl IntoResponse {
+    (
+        [
+            (header::CONTENT_TYPE, "text/javascript"),
+            (header::CACHE_CONTROL, "no-cache"),
+        ],
+        SERVICE_WORKER,
+    )
+}
+
+#[cfg(feature = "server")]
+async fn manifest() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "application/manifest+json")],
+        MANIFEST,
+    )
+}
+
+#[cfg(feature = "server")]
+async fn icon() -> impl IntoResponse {
+    (
+        [
+            (header::CONTENT_TYPE, "image/svg+xml"),
+            (header::CACHE_CONTROL, "public, max-age=86400"),
+        ],
+        ICON,
+    )
+}
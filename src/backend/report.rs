@@ -0,0 +1,496 @@
+//! Printable per-kid summaries of a week or a month, for sharing with family or
+//! teachers: the cycle total next to the previous one, and the notes day by day.
+//! Rendered as a standalone HTML page or a PDF, both written by hand so the report
+//! needs no template or PDF library.
+use crate::backend::clock;
+use crate::backend::kids::find_kid;
+use crate::backend::repository::{KidsRepository, NotesRepository};
+use crate::backend::stats::{cycle_bounds, shift_cycles};
+use crate::backend::validation::validate_utc_offset;
+use crate::models::{ExportFile, ReportFormat, ReportPeriod};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
+use dioxus::prelude::ServerFnError;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// How far back a report can go.
+const MAX_CYCLES_AGO: u32 = 120;
+
+/// Everything a report shows.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KidReport {
+    pub name: String,
+    pub emoji: Option<String>,
+    pub period: ReportPeriod,
+    pub start: NaiveDate,
+    /// Exclusive.
+    pub end: NaiveDate,
+    /// The cycle hasn't ended yet.
+    pub in_progress: bool,
+    pub total: i32,
+    pub positive: u32,
+    pub negative: u32,
+    pub previous_total: i32,
+    /// Only days with notes, oldest first.
+    pub days: Vec<ReportDay>,
+    /// In the reader's time zone, like every other date and time here.
+    pub generated_at: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReportDay {
+    pub date: NaiveDate,
+    /// Time and quantity of each note, in order.
+    pub notes: Vec<(NaiveTime, i32)>,
+}
+
+impl ReportDay {
+    fn positive(&self) -> usize {
+        self.notes.iter().filter(|(_, q)| *q > 0).count()
+    }
+
+    fn negative(&self) -> usize {
+        self.notes.iter().filter(|(_, q)| *q < 0).count()
+    }
+
+    fn total(&self) -> i32 {
+        self.notes.iter().map(|(_, q)| q).sum()
+    }
+
+    fn times(&self) -> Vec<String> {
+        self.notes
+            .iter()
+            .map(|(time, q)| format!("{} {}", time.format("%H:%M"), signed(*q)))
+            .collect()
+    }
+}
+
+impl KidReport {
+    fn unit(&self) -> &'static str {
+        match self.period {
+            ReportPeriod::Week => "week",
+            ReportPeriod::Month => "month",
+        }
+    }
+
+    /// "Week of 2 Mar 2026" or "March 2026".
+    fn title(&self) -> String {
+        match self.period {
+            ReportPeriod::Week => format!("Week of {}", self.start.format("%-d %b %Y")),
+            ReportPeriod::Month => self.start.format("%B %Y").to_string(),
+        }
+    }
+
+    /// "2 Mar – 8 Mar 2026", plus a marker while the cycle is still running.
+    fn range(&self) -> String {
+        let last = self.end.pred_opt().unwrap_or(self.end);
+        let range = format!(
+            "{} – {}",
+            self.start.format("%-d %b"),
+            last.format("%-d %b %Y")
+        );
+        if self.in_progress {
+            format!("{range} (in progress)")
+        } else {
+            range
+        }
+    }
+
+    fn file_stem(&self) -> String {
+        let slug: String = self
+            .name
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '-' })
+            .collect();
+        let period = match self.period {
+            ReportPeriod::Week => self.start.format("%G-W%V"),
+            ReportPeriod::Month => self.start.format("%Y-%m"),
+        };
+        format!("hot_dog-{slug}-{period}")
+    }
+}
+
+/// "+3", "0" or "-2".
+fn signed(n: i32) -> String {
+    if n > 0 {
+        format!("+{n}")
+    } else {
+        n.to_string()
+    }
+}
+
+/// Gathers the report for `kid_id` over the week or month `cycles_ago` cycles back
+/// (0 is the current one). Archived kids can be reported on too. Weeks, months,
+/// days and times are those of the reader's time zone, `utc_offset_minutes` ahead
+/// of UTC.
+pub async fn kid_report(
+    repo: &(impl KidsRepository + NotesRepository),
+    kid_id: u32,
+    period: ReportPeriod,
+    cycles_ago: u32,
+    utc_offset_minutes: i32,
+) -> Result<KidReport, ServerFnError> {
+    validate_utc_offset(utc_offset_minutes)?;
+    if cycles_ago > MAX_CYCLES_AGO {
+        return Err(ServerFnError::new(format!(
+            "Reports go back at most {MAX_CYCLES_AGO} cycles"
+        )));
+    }
+    let kid = find_kid(repo, kid_id)
+        .await?
+        .ok_or_else(|| ServerFnError::new("Kid not found".to_string()))?;
+    let granularity = match period {
+        ReportPeriod::Week => "WEEKLY",
+        ReportPeriod::Month => "MONTHLY",
+    };
+
+    // Notes are stored in UTC; everything below is worked out in local time
+    let offset = TimeDelta::minutes(utc_offset_minutes.into());
+    let now = clock::now() + offset;
+    let (current, _) = cycle_bounds(granularity, now.date())?;
+    let out_of_range = || ServerFnError::new("Cycle range out of bounds".to_string());
+    let start =
+        shift_cycles(granularity, current.date(), cycles_ago, false).ok_or_else(out_of_range)?;
+    let previous = shift_cycles(granularity, start, 1, false).ok_or_else(out_of_range)?;
+    let (start, end) = cycle_bounds(granularity, start)?;
+    let (previous, _) = cycle_bounds(granularity, previous)?;
+
+    let mut days: BTreeMap<NaiveDate, Vec<(NaiveTime, i32)>> = BTreeMap::new();
+    let mut previous_total = 0;
+    for note in repo
+        .kid_notes_between(kid_id, previous - offset, end - offset)
+        .await?
+    {
+        let occurred_at = note.occurred_at + offset;
+        if occurred_at < start {
+            previous_total += note.quantity;
+        } else {
+            days.entry(occurred_at.date())
+                .or_default()
+                .push((occurred_at.time(), note.quantity));
+        }
+    }
+    let days: Vec<ReportDay> = days
+        .into_iter()
+        .map(|(date, notes)| ReportDay { date, notes })
+        .collect();
+
+    Ok(KidReport {
+        name: kid.name,
+        emoji: kid.emoji,
+        period,
+        start: start.date(),
+        end: end.date(),
+        in_progress: end > now,
+        total: days.iter().map(ReportDay::total).sum(),
+        positive: days.iter().map(|d| d.positive() as u32).sum(),
+        negative: days.iter().map(|d| d.negative() as u32).sum(),
+        previous_total,
+        days,
+        generated_at: now,
+    })
+}
+
+/// The report for `kid_id` as a downloadable HTML page or PDF. See `kid_report`.
+pub async fn report_file(
+    repo: &(impl KidsRepository + NotesRepository),
+    kid_id: u32,
+    period: ReportPeriod,
+    cycles_ago: u32,
+    utc_offset_minutes: i32,
+    format: ReportFormat,
+) -> Result<ExportFile, ServerFnError> {
+    let report = kid_report(repo, kid_id, period, cycles_ago, utc_offset_minutes).await?;
+    let stem = report.file_stem();
+    Ok(match format {
+        ReportFormat::Html => ExportFile {
+            file_name: format!("{stem}.html"),
+            content_type: "text/html".to_string(),
+            bytes: render_html(&report).into_bytes(),
+        },
+        ReportFormat::Pdf => ExportFile {
+            file_name: format!("{stem}.pdf"),
+            content_type: "application/pdf".to_string(),
+            bytes: render_pdf(&report),
+        },
+    })
+}
+
+const STYLE: &str = "
+body { font-family: system-ui, sans-serif; color: #111827; max-width: 42rem; margin: 2rem auto; padding: 0 1rem; }
+h1 { font-size: 1.5rem; margin: 0; }
+.range { color: #6b7280; margin: 0.25rem 0 1.5rem; }
+.summary { display: flex; gap: 0.75rem; margin-bottom: 1.5rem; }
+.summary div { flex: 1; border: 1px solid #e5e7eb; border-radius: 0.5rem; padding: 0.75rem; }
+.summary strong { display: block; font-size: 1.5rem; }
+.summary span { color: #6b7280; font-size: 0.8rem; }
+table { width: 100%; border-collapse: collapse; font-size: 0.9rem; }
+th, td { text-align: left; padding: 0.35rem 0.5rem; border-bottom: 1px solid #e5e7eb; }
+.num { text-align: right; }
+.times { color: #6b7280; }
+footer { margin-top: 1.5rem; color: #9ca3af; font-size: 0.75rem; }
+.print { float: right; padding: 0.4rem 0.9rem; border: 1px solid #d1d5db; border-radius: 0.375rem; background: white; cursor: pointer; }
+@media print { .print { display: none; } body { margin: 0; } }
+";
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A standalone page with a Print button that hides itself when printed.
+pub fn render_html(report: &KidReport) -> String {
+    let name = escape(&report.name);
+    let heading = match &report.emoji {
+        Some(emoji) => format!("{} {name}", escape(emoji)),
+        None => name.clone(),
+    };
+    let title = report.title();
+
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{name} – {title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n\
+         <button class=\"print\" onclick=\"window.print()\">Print</button>\n\
+         <h1>{heading}</h1>\n<p class=\"range\">{title} · {}</p>\n",
+        escape(&report.range())
+    );
+    html.push_str("<div class=\"summary\">\n");
+    for (value, label) in [
+        (signed(report.total), "Total".to_string()),
+        (report.positive.to_string(), "+1 notes".to_string()),
+        (report.negative.to_string(), "-1 notes".to_string()),
+        (signed(report.previous_total), format!("Previous {}", report.unit())),
+    ] {
+        let _ = writeln!(html, "<div><strong>{value}</strong><span>{label}</span></div>");
+    }
+    html.push_str("</div>\n<table>\n<thead><tr><th>Day</th><th class=\"num\">+1</th><th class=\"num\">-1</th><th class=\"num\">Total</th><th>Notes</th></tr></thead>\n<tbody>\n");
+    if report.days.is_empty() {
+        let _ = writeln!(
+            html,
+            "<tr><td colspan=\"5\">No notes this {}.</td></tr>",
+            report.unit()
+        );
+    }
+    for day in &report.days {
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"times\">{}</td></tr>",
+            day.date.format("%a %-d %b"),
+            day.positive(),
+            day.negative(),
+            signed(day.total()),
+            day.times().join(", ")
+        );
+    }
+    let _ = write!(
+        html,
+        "</tbody>\n</table>\n<footer>Generated {} by Hot Dog</footer>\n</body>\n</html>\n",
+        report.generated_at.format("%-d %b %Y %H:%M")
+    );
+    html
+}
+
+/// A4 pages of plain text in the standard Helvetica fonts.
+pub fn render_pdf(report: &KidReport) -> Vec<u8> {
+    let mut pdf = Pdf::new();
+    // The standard fonts have no emoji, so only the name goes in the heading
+    pdf.row(&[(0.0, &report.name)], Font::Bold, 20.0);
+    pdf.row(
+        &[(0.0, &format!("{} · {}", report.title(), report.range()))],
+        Font::Regular,
+        11.0,
+    );
+    pdf.gap(12.0);
+    let summary = format!(
+        "Total {}     +1 notes {}     -1 notes {}     Previous {} {}",
+        signed(report.total),
+        report.positive,
+        report.negative,
+        report.unit(),
+        signed(report.previous_total)
+    );
+    pdf.row(&[(0.0, &summary)], Font::Bold, 12.0);
+    pdf.gap(12.0);
+
+    const COLUMNS: [f32; 5] = [0.0, 110.0, 145.0, 180.0, 225.0];
+    let header = ["Day", "+1", "-1", "Total", "Notes"];
+    pdf.row(
+        &COLUMNS.iter().copied().zip(header).collect::<Vec<_>>(),
+        Font::Bold,
+        10.0,
+    );
+    if report.days.is_empty() {
+        pdf.row(
+            &[(0.0, &format!("No notes this {}.", report.unit()))],
+            Font::Regular,
+            10.0,
+        );
+    }
+    for day in &report.days {
+        let cells = [
+            day.date.format("%a %-d %b").to_string(),
+            day.positive().to_string(),
+            day.negative().to_string(),
+            signed(day.total()),
+        ];
+        // Four notes fit on a line; the rest continue under the first
+        let times = day.times();
+        let mut chunks = times.chunks(4);
+        let first = chunks.next().map(|c| c.join(", ")).unwrap_or_default();
+        let mut row: Vec<(f32, &str)> =
+            COLUMNS.iter().copied().zip(cells.iter().map(String::as_str)).collect();
+        row.push((COLUMNS[4], first.as_str()));
+        pdf.row(&row, Font::Regular, 10.0);
+        for chunk in chunks {
+            pdf.row(&[(COLUMNS[4], &chunk.join(", "))], Font::Regular, 10.0);
+        }
+    }
+    pdf.gap(12.0);
+    pdf.row(
+        &[(
+            0.0,
+            &format!(
+                "Generated {} by Hot Dog",
+                report.generated_at.format("%-d %b %Y %H:%M")
+            ),
+        )],
+        Font::Regular,
+        8.0,
+    );
+    pdf.finish()
+}
+
+#[derive(Clone, Copy)]
+enum Font {
+    Regular,
+    Bold,
+}
+
+/// Lays out rows of text top to bottom, starting a new page when one fills up.
+struct Pdf {
+    pages: Vec<Vec<u8>>,
+    current: Vec<u8>,
+    y: f32,
+}
+
+impl Pdf {
+    const WIDTH: f32 = 595.0;
+    const HEIGHT: f32 = 842.0;
+    const MARGIN: f32 = 50.0;
+
+    fn new() -> Self {
+        Pdf {
+            pages: Vec::new(),
+            current: Vec::new(),
+            y: Self::HEIGHT - Self::MARGIN,
+        }
+    }
+
+    fn gap(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    /// Writes `(x, text)` cells on one line, `x` measured from the left margin.
+    fn row(&mut self, cells: &[(f32, impl AsRef<str>)], font: Font, size: f32) {
+        let leading = size * 1.4;
+        if self.y - leading < Self::MARGIN {
+            self.new_page();
+        }
+        self.y -= leading;
+        let font = match font {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+        };
+        for (x, text) in cells {
+            let position = format!(
+                "BT /{font} {size} Tf {:.1} {:.1} Td (",
+                Self::MARGIN + x,
+                self.y
+            );
+            self.current.extend(position.into_bytes());
+            self.current.extend(pdf_string(text.as_ref()));
+            self.current.extend_from_slice(b") Tj ET\n");
+        }
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(std::mem::take(&mut self.current));
+        self.y = Self::HEIGHT - Self::MARGIN;
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.new_page();
+        let count = self.pages.len();
+        let kids: Vec<String> = (0..count).map(|i| format!("{} 0 R", 5 + 2 * i)).collect();
+        let font = |name: &str| {
+            format!("<< /Type /Font /Subtype /Type1 /BaseFont /{name} /Encoding /WinAnsiEncoding >>")
+                .into_bytes()
+        };
+        let mut objects = vec![
+            b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+            format!("<< /Type /Pages /Kids [{}] /Count {count} >>", kids.join(" ")).into_bytes(),
+            font("Helvetica"),
+            font("Helvetica-Bold"),
+        ];
+        for (i, content) in self.pages.into_iter().enumerate() {
+            objects.push(
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                     /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                    Self::WIDTH,
+                    Self::HEIGHT,
+                    6 + 2 * i
+                )
+                .into_bytes(),
+            );
+            let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+            stream.extend(content);
+            stream.extend_from_slice(b"\nendstream");
+            objects.push(stream);
+        }
+
+        let mut out = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.extend(format!("{} 0 obj\n", i + 1).into_bytes());
+            out.extend_from_slice(object);
+            out.extend_from_slice(b"\nendobj\n");
+        }
+        let xref = out.len();
+        out.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).into_bytes());
+        for offset in offsets {
+            out.extend(format!("{offset:010} 00000 n \n").into_bytes());
+        }
+        out.extend(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+                objects.len() + 1
+            )
+            .into_bytes(),
+        );
+        out
+    }
+}
+
+/// Encodes `text` for a PDF string literal. The standard fonts cover Latin-1 (close
+/// enough to WinAnsi for names); other characters print as "?", except the dash
+/// used in ranges.
+fn pdf_string(text: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => bytes.extend_from_slice(&[b'\\', c as u8]),
+            '–' => bytes.push(0x96),
+            c if (c as u32) < 0x20 => {}
+            c if (c as u32) <= 0xff => bytes.push(c as u8),
+            _ => bytes.push(b'?'),
+        }
+    }
+    bytes
+}
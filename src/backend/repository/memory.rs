@@ -0,0 +1,563 @@
+use super::{
+    AuditRepository, AvatarsRepository, ImportBatch, ImportCounts, ImportRepository, ImportedKid,
+    KidsRepository, NewAuditEntry, NoteCounts, NoteTotals, NotesRepository, Settings,
+    SettingsRepository,
+};
+use crate::backend::clock::now;
+use crate::backend::validation::ValidationError;
+use crate::models::{AuditAction, AuditEntry, HeatmapCell, KidLimits, KidSummary, NoteRecord};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, TimeDelta, Timelike};
+use dioxus::prelude::ServerFnError;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+/// Keeps everything in memory, for tests that don't need a database; nothing is
+/// persisted.
+#[derive(Default)]
+pub struct InMemoryRepository {
+    state: Mutex<State>,
+}
+
+#[derive(Clone, Default)]
+struct State {
+    settings: Settings,
+    kids: Vec<StoredKid>,
+    notes: Vec<StoredNote>,
+    avatars: HashMap<u32, Vec<u8>>,
+    audit: Vec<AuditEntry>,
+    next_kid_id: u32,
+}
+
+#[derive(Clone)]
+struct StoredKid {
+    summary: KidSummary,
+    archived_at: Option<NaiveDateTime>,
+}
+
+#[derive(Clone)]
+struct StoredNote {
+    kid_id: u32,
+    quantity: i32,
+    occurred_at: NaiveDateTime,
+    created_at: NaiveDateTime,
+    idempotency_key: Option<String>,
+}
+
+impl InMemoryRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        // A panic while holding the lock can't leave the state half-written in a
+        // way that matters for tests, so keep going
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl State {
+    fn kid_mut(&mut self, kid_id: u32) -> Result<&mut StoredKid, ServerFnError> {
+        self.kids
+            .iter_mut()
+            .find(|k| k.summary.id == kid_id)
+            .ok_or_else(|| ServerFnError::new("Kid not found".to_string()))
+    }
+
+    fn active_names(&self, except: Option<u32>) -> Vec<String> {
+        self.kids
+            .iter()
+            .filter(|k| k.archived_at.is_none() && Some(k.summary.id) != except)
+            .map(|k| k.summary.name.clone())
+            .collect()
+    }
+
+    fn next_position(&self) -> u32 {
+        self.kids.iter().map(|k| k.summary.position).max().unwrap_or(0) + 1
+    }
+
+    fn append(&mut self, entry: NewAuditEntry) {
+        let id = self.audit.len() as u32 + 1;
+        self.audit.push(AuditEntry {
+            id,
+            actor: entry.actor,
+            action: entry.action,
+            kid_id: entry.kid_id,
+            kid_name: entry.kid_name,
+            before: entry.before,
+            after: entry.after,
+            created_at: now(),
+        });
+    }
+}
+
+impl SettingsRepository for InMemoryRepository {
+    async fn settings(&self) -> Result<Settings, ServerFnError> {
+        Ok(self.state().settings.clone())
+    }
+
+    async fn set_granularity(
+        &self,
+        granularity: &str,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError> {
+        let mut state = self.state();
+        state.settings.granularity = granularity.to_string();
+        state.append(audit);
+        Ok(())
+    }
+
+    async fn set_leaderboard(
+        &self,
+        enabled: bool,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError> {
+        let mut state = self.state();
+        state.settings.leaderboard = enabled;
+        state.append(audit);
+        Ok(())
+    }
+
+    async fn set_limits(
+        &self,
+        limits: KidLimits,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError> {
+        let mut state = self.state();
+        state.settings.limits = limits;
+        state.append(audit);
+        Ok(())
+    }
+
+    async fn set_backdate_closed_cycles(
+        &self,
+        enabled: bool,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError> {
+        let mut state = self.state();
+        state.settings.backdate_closed_cycles = enabled;
+        state.append(audit);
+        Ok(())
+    }
+}
+
+impl KidsRepository for InMemoryRepository {
+    async fn list_active(&self) -> Result<Vec<KidSummary>, ServerFnError> {
+        let state = self.state();
+        let mut kids: Vec<KidSummary> = state
+            .kids
+            .iter()
+            .filter(|k| k.archived_at.is_none())
+            .map(|k| k.summary.clone())
+            .collect();
+        kids.sort_by_key(|k| (k.position, k.id));
+        Ok(kids)
+    }
+
+    async fn list_archived(&self) -> Result<Vec<KidSummary>, ServerFnError> {
+        let state = self.state();
+        let mut kids: Vec<(NaiveDateTime, KidSummary)> = state
+            .kids
+            .iter()
+            .filter_map(|k| k.archived_at.map(|at| (at, k.summary.clone())))
+            .collect();
+        kids.sort_by(|a, b| b.0.cmp(&a.0));
+        Ok(kids.into_iter().map(|(_, kid)| kid).collect())
+    }
+
+    async fn add(
+        &self,
+        name: &str,
+        check: impl FnOnce(&[String]) -> Result<(), ValidationError>,
+        audit: impl FnOnce(&KidSummary) -> NewAuditEntry,
+    ) -> Result<KidSummary, ServerFnError> {
+        let mut state = self.state();
+        check(&state.active_names(None))?;
+
+        state.next_kid_id += 1;
+        let summary = KidSummary {
+            id: state.next_kid_id,
+            name: name.to_string(),
+            position: state.next_position(),
+            color: None,
+            emoji: None,
+            birthdate: None,
+            avatar_version: None,
+        };
+        state.kids.push(StoredKid {
+            summary: summary.clone(),
+            archived_at: None,
+        });
+        state.append(audit(&summary));
+        Ok(summary)
+    }
+
+    async fn rename(
+        &self,
+        kid_id: u32,
+        name: &str,
+        check: impl FnOnce(&[String]) -> Result<(), ValidationError>,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError> {
+        let mut state = self.state();
+        check(&state.active_names(Some(kid_id)))?;
+        state.kid_mut(kid_id)?.summary.name = name.to_string();
+        state.append(audit);
+        Ok(())
+    }
+
+    async fn reorder(
+        &self,
+        kid_ids: &[u32],
+        check: impl FnOnce(&[u32]) -> Result<(), ValidationError>,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError> {
+        let mut state = self.state();
+        let active_ids: Vec<u32> = state
+            .kids
+            .iter()
+            .filter(|k| k.archived_at.is_none())
+            .map(|k| k.summary.id)
+            .collect();
+        check(&active_ids)?;
+        for (position, kid_id) in kid_ids.iter().enumerate() {
+            if let Ok(kid) = state.kid_mut(*kid_id) {
+                kid.summary.position = position as u32 + 1;
+            }
+        }
+        state.append(audit);
+        Ok(())
+    }
+
+    async fn update_profile(
+        &self,
+        kid_id: u32,
+        color: Option<String>,
+        emoji: Option<String>,
+        birthdate: Option<NaiveDate>,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError> {
+        let mut state = self.state();
+        if let Ok(kid) = state.kid_mut(kid_id) {
+            kid.summary.color = color;
+            kid.summary.emoji = emoji;
+            kid.summary.birthdate = birthdate;
+        }
+        state.append(audit);
+        Ok(())
+    }
+
+    async fn set_avatar_version(
+        &self,
+        kid_id: u32,
+        version: Option<i64>,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError> {
+        let mut state = self.state();
+        if let Ok(kid) = state.kid_mut(kid_id) {
+            kid.summary.avatar_version = version;
+        }
+        state.append(audit);
+        Ok(())
+    }
+
+    async fn archive(&self, kid_id: u32, audit: NewAuditEntry) -> Result<(), ServerFnError> {
+        let mut state = self.state();
+        let archived = match state.kid_mut(kid_id) {
+            Ok(kid) if kid.archived_at.is_none() => {
+                kid.archived_at = Some(now());
+                true
+            }
+            _ => false,
+        };
+        if archived {
+            state.append(audit);
+        }
+        Ok(())
+    }
+
+    async fn restore(
+        &self,
+        kid_id: u32,
+        check: impl FnOnce(&str, &[String]) -> Result<(), ValidationError>,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError> {
+        let mut state = self.state();
+        let kid = state.kid_mut(kid_id)?;
+        if kid.archived_at.is_none() {
+            return Ok(());
+        }
+        let name = kid.summary.name.clone();
+        check(&name, &state.active_names(None))?;
+
+        let position = state.next_position();
+        let kid = state.kid_mut(kid_id)?;
+        kid.archived_at = None;
+        kid.summary.position = position;
+        state.append(audit);
+        Ok(())
+    }
+
+    async fn delete(
+        &self,
+        kid_id: u32,
+        check: impl FnOnce(bool) -> Result<(), ServerFnError>,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError> {
+        let mut state = self.state();
+        let archived = state.kid_mut(kid_id)?.archived_at.is_some();
+        check(archived)?;
+
+        state.notes.retain(|n| n.kid_id != kid_id);
+        state.avatars.remove(&kid_id);
+        state.kids.retain(|k| k.summary.id != kid_id);
+        state.append(audit);
+        Ok(())
+    }
+}
+
+impl AvatarsRepository for InMemoryRepository {
+    async fn put_avatar(
+        &self,
+        kid_id: u32,
+        jpeg: Vec<u8>,
+        version: i64,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError> {
+        let mut state = self.state();
+        state.avatars.insert(kid_id, jpeg);
+        if let Ok(kid) = state.kid_mut(kid_id) {
+            kid.summary.avatar_version = Some(version);
+        }
+        state.append(audit);
+        Ok(())
+    }
+
+    async fn avatar(&self, kid_id: u32) -> Result<Option<Vec<u8>>, ServerFnError> {
+        Ok(self.state().avatars.get(&kid_id).cloned())
+    }
+
+    async fn delete_avatar(&self, kid_id: u32, audit: NewAuditEntry) -> Result<(), ServerFnError> {
+        let mut state = self.state();
+        state.avatars.remove(&kid_id);
+        if let Ok(kid) = state.kid_mut(kid_id) {
+            kid.summary.avatar_version = None;
+        }
+        state.append(audit);
+        Ok(())
+    }
+}
+
+impl NotesRepository for InMemoryRepository {
+    async fn record(
+        &self,
+        kid_id: u32,
+        quantity: i32,
+        idempotency_key: Option<&str>,
+        occurred_at: NaiveDateTime,
+        audit: NewAuditEntry,
+    ) -> Result<bool, ServerFnError> {
+        let mut state = self.state();
+        if let Some(key) = idempotency_key {
+            if state
+                .notes
+                .iter()
+                .any(|n| n.idempotency_key.as_deref() == Some(key))
+            {
+                return Ok(false);
+            }
+        }
+        state.notes.push(StoredNote {
+            kid_id,
+            quantity,
+            occurred_at,
+            created_at: now(),
+            idempotency_key: idempotency_key.map(str::to_string),
+        });
+        state.append(audit);
+        Ok(true)
+    }
+
+    async fn totals_between(
+        &self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<HashMap<u32, NoteTotals>, ServerFnError> {
+        let state = self.state();
+        let mut totals: HashMap<u32, NoteTotals> = HashMap::new();
+        let in_range = |n: &&StoredNote| start <= n.occurred_at && n.occurred_at < end;
+        for note in state.notes.iter().filter(in_range) {
+            let entry = totals.entry(note.kid_id).or_default();
+            entry.total += note.quantity;
+            entry.latest_note = entry.latest_note.max(Some(note.occurred_at));
+        }
+        Ok(totals)
+    }
+
+    async fn note_counts(&self) -> Result<HashMap<u32, NoteCounts>, ServerFnError> {
+        let state = self.state();
+        let mut counts: HashMap<u32, NoteCounts> = HashMap::new();
+        for note in &state.notes {
+            let entry = counts.entry(note.kid_id).or_default();
+            if note.quantity > 0 {
+                entry.positive += 1;
+            } else if note.quantity < 0 {
+                entry.negative += 1;
+            }
+        }
+        Ok(counts)
+    }
+
+    async fn activity(&self, utc_offset_minutes: i32) -> Result<Vec<HeatmapCell>, ServerFnError> {
+        let state = self.state();
+        let mut counts: HashMap<(u32, u32), u32> = HashMap::new();
+        for note in &state.notes {
+            let local = note.occurred_at + TimeDelta::minutes(utc_offset_minutes as i64);
+            let key = (local.weekday().num_days_from_monday(), local.hour());
+            *counts.entry(key).or_default() += 1;
+        }
+        Ok(counts
+            .into_iter()
+            .map(|((weekday, hour), count)| HeatmapCell {
+                weekday,
+                hour,
+                count,
+            })
+            .collect())
+    }
+
+    async fn all_notes(&self) -> Result<Vec<NoteRecord>, ServerFnError> {
+        let state = self.state();
+        let mut notes: Vec<NoteRecord> = state
+            .notes
+            .iter()
+            .map(|note| NoteRecord {
+                kid_id: note.kid_id,
+                quantity: note.quantity,
+                occurred_at: note.occurred_at,
+                created_at: note.created_at,
+                idempotency_key: note.idempotency_key.clone(),
+            })
+            .collect();
+        // Stable, so notes at the same time keep the order they were recorded in
+        notes.sort_by_key(|note| note.occurred_at);
+        Ok(notes)
+    }
+
+    async fn kid_notes_between(
+        &self,
+        kid_id: u32,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<Vec<NoteRecord>, ServerFnError> {
+        let mut notes = self.all_notes().await?;
+        notes.retain(|n| n.kid_id == kid_id && start <= n.occurred_at && n.occurred_at < end);
+        Ok(notes)
+    }
+}
+
+impl ImportRepository for InMemoryRepository {
+    async fn import(
+        &self,
+        batch: &ImportBatch,
+        commit: bool,
+        check: impl FnOnce(&[String]) -> Result<(), ValidationError>,
+        audit: impl FnOnce(&ImportCounts) -> NewAuditEntry,
+    ) -> Result<ImportCounts, ServerFnError> {
+        let mut state = self.state();
+        check(&state.active_names(None))?;
+
+        // Work on a copy, kept only when committing
+        let mut draft = state.clone();
+        if let Some(settings) = &batch.settings {
+            draft.settings = settings.clone();
+        }
+        let mut counts = ImportCounts::default();
+        let mut kid_ids = Vec::with_capacity(batch.kids.len());
+        for kid in &batch.kids {
+            match kid {
+                ImportedKid::Existing(id) => kid_ids.push(*id),
+                ImportedKid::New {
+                    name,
+                    color,
+                    emoji,
+                    birthdate,
+                    archived,
+                } => {
+                    draft.next_kid_id += 1;
+                    let summary = KidSummary {
+                        id: draft.next_kid_id,
+                        name: name.clone(),
+                        position: draft.next_position(),
+                        color: color.clone(),
+                        emoji: emoji.clone(),
+                        birthdate: *birthdate,
+                        avatar_version: None,
+                    };
+                    kid_ids.push(summary.id);
+                    draft.kids.push(StoredKid {
+                        summary,
+                        archived_at: archived.then(now),
+                    });
+                    counts.kids_added += 1;
+                }
+            }
+        }
+
+        for note in &batch.notes {
+            let duplicate = note.idempotency_key.as_ref().is_some_and(|key| {
+                draft
+                    .notes
+                    .iter()
+                    .any(|n| n.idempotency_key.as_ref() == Some(key))
+            });
+            if duplicate {
+                counts.notes_skipped += 1;
+                continue;
+            }
+            let kid_id = *kid_ids
+                .get(note.kid)
+                .ok_or_else(|| ServerFnError::new("Kid not found".to_string()))?;
+            draft.notes.push(StoredNote {
+                kid_id,
+                quantity: note.quantity,
+                occurred_at: note.occurred_at,
+                created_at: note.created_at,
+                idempotency_key: note.idempotency_key.clone(),
+            });
+            counts.notes_added += 1;
+        }
+
+        if commit {
+            draft.append(audit(&counts));
+            *state = draft;
+        }
+        Ok(counts)
+    }
+}
+
+impl AuditRepository for InMemoryRepository {
+    async fn append(&self, entry: NewAuditEntry) -> Result<(), ServerFnError> {
+        self.state().append(entry);
+        Ok(())
+    }
+
+    async fn audit_entries(
+        &self,
+        action: Option<AuditAction>,
+        start: Option<NaiveDateTime>,
+        end: Option<NaiveDateTime>,
+        limit: u32,
+    ) -> Result<Vec<AuditEntry>, ServerFnError> {
+        let state = self.state();
+        Ok(state
+            .audit
+            .iter()
+            .rev()
+            .filter(|e| action.is_none_or(|a| e.action == a))
+            .filter(|e| start.is_none_or(|s| e.created_at >= s))
+            .filter(|e| end.is_none_or(|s| e.created_at < s))
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+}
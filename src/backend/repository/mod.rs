@@ -0,0 +1,309 @@
+//! Storage behind the backend. Business rules (limits, cycles, validation) live in
+//! `kids` and `stats` and only go through these traits, so they run the same against
+//! libSQL or the in-memory store.
+//!
+//! Writes that depend on a check (room for one more kid, unique names, ...) take the
+//! check as a closure; implementations run it inside the same write transaction.
+//! Writes also take the audit entry describing them, appended in that transaction,
+//! so the log has exactly the changes that went through.
+#[cfg(test)]
+mod memory;
+mod sql;
+
+#[cfg(test)]
+pub use memory::InMemoryRepository;
+pub use sql::LibsqlRepository;
+
+use crate::backend::turso::{get_database, get_db};
+use crate::backend::validation::ValidationError;
+use crate::models::{AuditAction, AuditEntry, HeatmapCell, KidLimits, KidSummary, NoteRecord};
+use chrono::{NaiveDate, NaiveDateTime};
+use dioxus::prelude::ServerFnError;
+use std::collections::HashMap;
+use tokio::sync::OnceCell;
+
+/// Family-wide settings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Settings {
+    /// DAILY, WEEKLY, MONTHLY or YEARLY
+    pub granularity: String,
+    pub leaderboard: bool,
+    pub limits: KidLimits,
+    /// Whether notes may be backdated into cycles that are already closed.
+    pub backdate_closed_cycles: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            granularity: "MONTHLY".to_string(),
+            leaderboard: false,
+            limits: KidLimits::default(),
+            backdate_closed_cycles: false,
+        }
+    }
+}
+
+/// Sum of a kid's notes over some range, with the time of the latest one.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NoteTotals {
+    pub total: i32,
+    pub latest_note: Option<NaiveDateTime>,
+}
+
+/// How many positive and negative notes a kid received.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NoteCounts {
+    pub positive: u32,
+    pub negative: u32,
+}
+
+/// An audit log entry about to be appended; the time comes from the clock.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NewAuditEntry {
+    pub actor: String,
+    pub action: AuditAction,
+    pub kid_id: Option<u32>,
+    pub kid_name: Option<String>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[allow(async_fn_in_trait)]
+pub trait SettingsRepository {
+    async fn settings(&self) -> Result<Settings, ServerFnError>;
+    async fn set_granularity(
+        &self,
+        granularity: &str,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError>;
+    async fn set_leaderboard(&self, enabled: bool, audit: NewAuditEntry)
+        -> Result<(), ServerFnError>;
+    async fn set_limits(&self, limits: KidLimits, audit: NewAuditEntry)
+        -> Result<(), ServerFnError>;
+    async fn set_backdate_closed_cycles(
+        &self,
+        enabled: bool,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError>;
+}
+
+#[allow(async_fn_in_trait)]
+pub trait KidsRepository {
+    /// Active kids in display order.
+    async fn list_active(&self) -> Result<Vec<KidSummary>, ServerFnError>;
+    /// Archived kids, most recently archived first.
+    async fn list_archived(&self) -> Result<Vec<KidSummary>, ServerFnError>;
+    /// Adds a kid at the end of the list. `check` gets the names of the active kids;
+    /// `audit` gets the kid as added.
+    async fn add(
+        &self,
+        name: &str,
+        check: impl FnOnce(&[String]) -> Result<(), ValidationError>,
+        audit: impl FnOnce(&KidSummary) -> NewAuditEntry,
+    ) -> Result<KidSummary, ServerFnError>;
+    /// Renames a kid. `check` gets the names of the other active kids.
+    async fn rename(
+        &self,
+        kid_id: u32,
+        name: &str,
+        check: impl FnOnce(&[String]) -> Result<(), ValidationError>,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError>;
+    /// Gives kids positions 1, 2, ... in the order listed. `check` gets the ids of
+    /// the active kids.
+    async fn reorder(
+        &self,
+        kid_ids: &[u32],
+        check: impl FnOnce(&[u32]) -> Result<(), ValidationError>,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError>;
+    async fn update_profile(
+        &self,
+        kid_id: u32,
+        color: Option<String>,
+        emoji: Option<String>,
+        birthdate: Option<NaiveDate>,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError>;
+    async fn set_avatar_version(
+        &self,
+        kid_id: u32,
+        version: Option<i64>,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError>;
+    /// Archives an active kid; archiving twice is a no-op and leaves no audit entry.
+    async fn archive(&self, kid_id: u32, audit: NewAuditEntry) -> Result<(), ServerFnError>;
+    /// Restores an archived kid at the end of the list; restoring an active kid is a
+    /// no-op. `check` gets the kid's name and the names of the active kids.
+    async fn restore(
+        &self,
+        kid_id: u32,
+        check: impl FnOnce(&str, &[String]) -> Result<(), ValidationError>,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError>;
+    /// Deletes a kid with their notes and stored photo. `check` gets whether the
+    /// kid is archived.
+    async fn delete(
+        &self,
+        kid_id: u32,
+        check: impl FnOnce(bool) -> Result<(), ServerFnError>,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError>;
+}
+
+/// Kids' photos, when they are kept in the database rather than on disk.
+#[allow(async_fn_in_trait)]
+pub trait AvatarsRepository {
+    /// Stores a kid's photo, replacing the previous one, and sets their avatar version
+    /// in the same transaction.
+    async fn put_avatar(
+        &self,
+        kid_id: u32,
+        jpeg: Vec<u8>,
+        version: i64,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError>;
+    async fn avatar(&self, kid_id: u32) -> Result<Option<Vec<u8>>, ServerFnError>;
+    /// Removes a kid's photo and clears their avatar version; removing a photo that
+    /// isn't there only clears the version.
+    async fn delete_avatar(&self, kid_id: u32, audit: NewAuditEntry) -> Result<(), ServerFnError>;
+}
+
+#[allow(async_fn_in_trait)]
+pub trait NotesRepository {
+    /// Records a note that happened at `occurred_at`; the time it is recorded is kept
+    /// separately. A note whose `idempotency_key` is already stored is skipped along
+    /// with its audit entry, and `false` is returned.
+    async fn record(
+        &self,
+        kid_id: u32,
+        quantity: i32,
+        idempotency_key: Option<&str>,
+        occurred_at: NaiveDateTime,
+        audit: NewAuditEntry,
+    ) -> Result<bool, ServerFnError>;
+    /// Totals per kid for notes that occurred in `[start, end)`.
+    async fn totals_between(
+        &self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<HashMap<u32, NoteTotals>, ServerFnError>;
+    /// Positive and negative note counts per kid, all time.
+    async fn note_counts(&self) -> Result<HashMap<u32, NoteCounts>, ServerFnError>;
+    /// Note counts per weekday and hour they occurred, shifted by `utc_offset_minutes`,
+    /// all time. Only non-empty cells.
+    async fn activity(&self, utc_offset_minutes: i32) -> Result<Vec<HeatmapCell>, ServerFnError>;
+    /// Every note of every kid, archived ones included, in the order they occurred.
+    async fn all_notes(&self) -> Result<Vec<NoteRecord>, ServerFnError>;
+    /// Notes of `kid_id` that occurred in `[start, end)`, in the order they occurred.
+    async fn kid_notes_between(
+        &self,
+        kid_id: u32,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<Vec<NoteRecord>, ServerFnError>;
+}
+
+/// Data to import in one go; see `ImportRepository`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImportBatch {
+    pub kids: Vec<ImportedKid>,
+    pub notes: Vec<ImportedNote>,
+    /// Replaces the stored settings, written first, e.g. when restoring a backup.
+    pub settings: Option<Settings>,
+}
+
+/// A kid from an import: one already stored, or one to add at the end of the list.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImportedKid {
+    Existing(u32),
+    New {
+        name: String,
+        color: Option<String>,
+        emoji: Option<String>,
+        birthdate: Option<NaiveDate>,
+        archived: bool,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportedNote {
+    /// Index into `ImportBatch::kids`.
+    pub kid: usize,
+    pub quantity: i32,
+    pub occurred_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+    pub idempotency_key: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ImportCounts {
+    pub kids_added: u32,
+    pub notes_added: u32,
+    /// Notes whose idempotency key was already stored.
+    pub notes_skipped: u32,
+}
+
+#[allow(async_fn_in_trait)]
+pub trait ImportRepository {
+    /// Writes `batch` in a single transaction, which is rolled back unless `commit`
+    /// is set, so a dry run reports exactly what would happen. `check` gets the names
+    /// of the active kids before anything is written; `audit` gets the counts of a
+    /// committed import.
+    async fn import(
+        &self,
+        batch: &ImportBatch,
+        commit: bool,
+        check: impl FnOnce(&[String]) -> Result<(), ValidationError>,
+        audit: impl FnOnce(&ImportCounts) -> NewAuditEntry,
+    ) -> Result<ImportCounts, ServerFnError>;
+}
+
+#[allow(async_fn_in_trait)]
+pub trait AuditRepository {
+    /// Appends an entry for an event that changes nothing else stored. Entries are
+    /// never changed or removed.
+    async fn append(&self, entry: NewAuditEntry) -> Result<(), ServerFnError>;
+    /// Up to `limit` entries, newest first, optionally of one action only and
+    /// made within `[start, end)`.
+    async fn audit_entries(
+        &self,
+        action: Option<AuditAction>,
+        start: Option<NaiveDateTime>,
+        end: Option<NaiveDateTime>,
+        limit: u32,
+    ) -> Result<Vec<AuditEntry>, ServerFnError>;
+}
+
+/// Everything the backend needs from storage.
+pub trait Repository:
+    SettingsRepository
+    + KidsRepository
+    + AvatarsRepository
+    + NotesRepository
+    + ImportRepository
+    + AuditRepository
+{
+}
+
+impl<T> Repository for T where
+    T: SettingsRepository
+        + KidsRepository
+        + AvatarsRepository
+        + NotesRepository
+        + ImportRepository
+        + AuditRepository
+{
+}
+
+static REPOSITORY: OnceCell<LibsqlRepository> = OnceCell::const_new();
+
+/// The store used by the app, reading on the connection from `get_db`.
+pub async fn repository() -> &'static LibsqlRepository {
+    REPOSITORY
+        .get_or_init(|| async {
+            LibsqlRepository::new(get_database().await.clone(), get_db().await.clone())
+        })
+        .await
+}
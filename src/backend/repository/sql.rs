@@ -0,0 +1,1011 @@
+use super::{
+    AuditRepository, AvatarsRepository, ImportBatch, ImportCounts, ImportRepository, ImportedKid,
+    KidsRepository, NewAuditEntry, NoteCounts, NoteTotals, NotesRepository, Settings,
+    SettingsRepository,
+};
+use crate::backend::clock;
+use crate::backend::validation::ValidationError;
+use crate::models::{AuditAction, AuditEntry, HeatmapCell, KidLimits, KidSummary, NoteRecord};
+use chrono::{NaiveDate, NaiveDateTime};
+use dioxus::prelude::ServerFnError;
+use libsql::params::IntoParams;
+use libsql::{de, Connection, Database, Transaction, TransactionBehavior};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// How long a write transaction waits for another one to finish on a local file.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Stores everything in a libSQL database, which must have the migrations applied.
+/// Reads and single statements share `conn`; each write transaction opens its own
+/// connection from `db`.
+pub struct LibsqlRepository {
+    db: Arc<Database>,
+    conn: Connection,
+}
+
+impl LibsqlRepository {
+    pub fn new(db: Arc<Database>, conn: Connection) -> Self {
+        LibsqlRepository { db, conn }
+    }
+
+    /// Starts a write transaction. `IMMEDIATE` takes the write lock up front, so checks
+    /// made inside the transaction still hold when its writes run.
+    ///
+    /// The transaction gets a fresh connection: on the shared one, requests running
+    /// at the same time would see each other's uncommitted writes, and a rollback
+    /// would discard them.
+    async fn begin_write(&self) -> Result<Transaction, ServerFnError> {
+        let conn = self
+            .db
+            .connect()
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        // Remote connections have no busy timeout to set; the server queues writes
+        let _ = conn.busy_timeout(BUSY_TIMEOUT);
+        conn.transaction_with_behavior(TransactionBehavior::Immediate)
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))
+    }
+
+    /// Runs a single statement and appends `audit` in one write transaction. Returns
+    /// the number of rows changed; a statement that changed nothing leaves no entry.
+    async fn execute_audited(
+        &self,
+        sql: &str,
+        params: impl IntoParams,
+        audit: &NewAuditEntry,
+    ) -> Result<u64, ServerFnError> {
+        let tx = self.begin_write().await?;
+        let changed = tx
+            .execute(sql, params)
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        if changed > 0 {
+            append_entry(&tx, audit).await?;
+        }
+        tx.commit()
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        Ok(changed)
+    }
+}
+
+fn timestamp(at: NaiveDateTime) -> String {
+    at.format(TIMESTAMP_FORMAT).to_string()
+}
+
+/// Reads rows of `kid_id, quantity, occurred_at, created_at, idempotency_key`.
+async fn note_records(mut rows: libsql::Rows) -> Result<Vec<NoteRecord>, ServerFnError> {
+    let parse = |value: &str| {
+        NaiveDateTime::parse_from_str(value, TIMESTAMP_FORMAT)
+            .map_err(|e| ServerFnError::new(e.to_string()))
+    };
+    let mut notes = Vec::new();
+    while let Some(row) = rows
+        .next()
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?
+    {
+        let row = de::from_row::<NoteRow>(&row).map_err(|e| ServerFnError::new(e.to_string()))?;
+        notes.push(NoteRecord {
+            kid_id: row.kid_id,
+            quantity: row.quantity,
+            occurred_at: parse(&row.occurred_at)?,
+            created_at: parse(&row.created_at)?,
+            idempotency_key: row.idempotency_key,
+        });
+    }
+    Ok(notes)
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[allow(dead_code)]
+struct SettingsRow {
+    id: u32,
+    granularity: String,
+    leaderboard: bool,
+    max_kids: u32,
+    max_name_length: u32,
+    backdate_closed_cycles: bool,
+    created_at: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct TotalsRow {
+    kid_id: u32,
+    total: i32,
+    latest_note: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct CountsRow {
+    kid_id: u32,
+    positive: u32,
+    negative: u32,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct AuditRow {
+    id: u32,
+    actor: String,
+    action: String,
+    kid_id: Option<u32>,
+    kid_name: Option<String>,
+    before: Option<String>,
+    after: Option<String>,
+    created_at: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct HeatmapRow {
+    weekday: u32,
+    hour: u32,
+    count: u32,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct NoteRow {
+    kid_id: u32,
+    quantity: i32,
+    occurred_at: String,
+    created_at: String,
+    idempotency_key: Option<String>,
+}
+
+async fn query_kid_summaries(
+    conn: &Connection,
+    sql: &str,
+) -> Result<Vec<KidSummary>, ServerFnError> {
+    let mut rows = conn
+        .query(sql, ())
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+
+    let mut kids = Vec::new();
+    while let Some(row) = rows
+        .next()
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?
+    {
+        let kid =
+            de::from_row::<KidSummary>(&row).map_err(|e| ServerFnError::new(e.to_string()))?;
+        kids.push(kid);
+    }
+    Ok(kids)
+}
+
+/// Returns the names of all active kids, optionally leaving one kid out.
+async fn active_kid_names(
+    conn: &Connection,
+    except: Option<u32>,
+) -> Result<Vec<String>, ServerFnError> {
+    let mut rows = conn
+        .query(
+            "SELECT name FROM kids WHERE archived_at IS NULL AND id != ?1",
+            libsql::params![except.unwrap_or(0)],
+        )
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+
+    let mut names = Vec::new();
+    while let Some(row) = rows
+        .next()
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?
+    {
+        names.push(row.get::<String>(0).map_err(|e| ServerFnError::new(e.to_string()))?);
+    }
+    Ok(names)
+}
+
+async fn active_kid_ids(conn: &Connection) -> Result<Vec<u32>, ServerFnError> {
+    let mut rows = conn
+        .query("SELECT id FROM kids WHERE archived_at IS NULL", ())
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+
+    let mut ids = Vec::new();
+    while let Some(row) = rows
+        .next()
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?
+    {
+        ids.push(row.get::<u32>(0).map_err(|e| ServerFnError::new(e.to_string()))?);
+    }
+    Ok(ids)
+}
+
+/// Appends an audit entry on `conn`, which may be a write transaction.
+async fn append_entry(conn: &Connection, entry: &NewAuditEntry) -> Result<(), ServerFnError> {
+    conn.execute(
+        "INSERT INTO audit_log (actor, action, kid_id, kid_name, before, after, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        libsql::params![
+            entry.actor.as_str(),
+            entry.action.as_str(),
+            entry.kid_id,
+            entry.kid_name.clone(),
+            entry.before.clone(),
+            entry.after.clone(),
+            timestamp(clock::now())
+        ],
+    )
+    .await
+    .map_err(|e| ServerFnError::new(e.to_string()))?;
+    Ok(())
+}
+
+impl SettingsRepository for LibsqlRepository {
+    async fn settings(&self) -> Result<Settings, ServerFnError> {
+        let conn = &self.conn;
+        let mut rows = conn
+            .query(
+                "SELECT id, granularity, leaderboard, max_kids, max_name_length, backdate_closed_cycles, created_at
+                 FROM settings LIMIT 1",
+                (),
+            )
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+
+        let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?
+        else {
+            return Err(ServerFnError::new("No settings found".to_string()));
+        };
+        let row =
+            de::from_row::<SettingsRow>(&row).map_err(|e| ServerFnError::new(e.to_string()))?;
+        Ok(Settings {
+            granularity: row.granularity,
+            leaderboard: row.leaderboard,
+            limits: KidLimits {
+                max_kids: row.max_kids,
+                max_name_length: row.max_name_length,
+            },
+            backdate_closed_cycles: row.backdate_closed_cycles,
+        })
+    }
+
+    async fn set_granularity(
+        &self,
+        granularity: &str,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError> {
+        self.execute_audited(
+            "UPDATE settings SET granularity = ?1 WHERE id = 1",
+            libsql::params![granularity],
+            &audit,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn set_leaderboard(
+        &self,
+        enabled: bool,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError> {
+        self.execute_audited(
+            "UPDATE settings SET leaderboard = ?1 WHERE id = 1",
+            libsql::params![enabled],
+            &audit,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn set_limits(
+        &self,
+        limits: KidLimits,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError> {
+        self.execute_audited(
+            "UPDATE settings SET max_kids = ?1, max_name_length = ?2 WHERE id = 1",
+            libsql::params![limits.max_kids, limits.max_name_length],
+            &audit,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn set_backdate_closed_cycles(
+        &self,
+        enabled: bool,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError> {
+        self.execute_audited(
+            "UPDATE settings SET backdate_closed_cycles = ?1 WHERE id = 1",
+            libsql::params![enabled],
+            &audit,
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+impl KidsRepository for LibsqlRepository {
+    async fn list_active(&self) -> Result<Vec<KidSummary>, ServerFnError> {
+        query_kid_summaries(
+            &self.conn,
+            "SELECT id, name, position, color, emoji, birthdate, avatar_version FROM kids
+             WHERE archived_at IS NULL ORDER BY position ASC, id ASC",
+        )
+        .await
+    }
+
+    async fn list_archived(&self) -> Result<Vec<KidSummary>, ServerFnError> {
+        query_kid_summaries(
+            &self.conn,
+            "SELECT id, name, position, color, emoji, birthdate, avatar_version FROM kids
+             WHERE archived_at IS NOT NULL ORDER BY archived_at DESC",
+        )
+        .await
+    }
+
+    async fn add(
+        &self,
+        name: &str,
+        check: impl FnOnce(&[String]) -> Result<(), ValidationError>,
+        audit: impl FnOnce(&KidSummary) -> NewAuditEntry,
+    ) -> Result<KidSummary, ServerFnError> {
+        let tx = self.begin_write().await?;
+        check(&active_kid_names(&tx, None).await?)?;
+
+        let mut rows = tx
+            .query(
+                "INSERT INTO kids (name, position, created_at)
+                 VALUES (?1, (SELECT COALESCE(MAX(position), 0) + 1 FROM kids), ?2)
+                 RETURNING id, name, position, color, emoji, birthdate, avatar_version",
+                libsql::params![name, timestamp(clock::now())],
+            )
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+
+        let kid = match rows
+            .next()
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?
+        {
+            Some(row) => {
+                de::from_row::<KidSummary>(&row).map_err(|e| ServerFnError::new(e.to_string()))?
+            }
+            None => {
+                return Err(ServerFnError::new(
+                    "Failed to retrieve inserted kid".to_string(),
+                ))
+            }
+        };
+        drop(rows);
+        append_entry(&tx, &audit(&kid)).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        Ok(kid)
+    }
+
+    async fn rename(
+        &self,
+        kid_id: u32,
+        name: &str,
+        check: impl FnOnce(&[String]) -> Result<(), ValidationError>,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError> {
+        let tx = self.begin_write().await?;
+        check(&active_kid_names(&tx, Some(kid_id)).await?)?;
+
+        let changed = tx
+            .execute(
+                "UPDATE kids SET name = ?1 WHERE id = ?2",
+                libsql::params![name, kid_id],
+            )
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        if changed == 0 {
+            return Err(ServerFnError::new("Kid not found".to_string()));
+        }
+        append_entry(&tx, &audit).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn reorder(
+        &self,
+        kid_ids: &[u32],
+        check: impl FnOnce(&[u32]) -> Result<(), ValidationError>,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError> {
+        let tx = self.begin_write().await?;
+        check(&active_kid_ids(&tx).await?)?;
+
+        for (position, kid_id) in kid_ids.iter().enumerate() {
+            tx.execute(
+                "UPDATE kids SET position = ?1 WHERE id = ?2",
+                libsql::params![position as u32 + 1, *kid_id],
+            )
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        }
+        append_entry(&tx, &audit).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn update_profile(
+        &self,
+        kid_id: u32,
+        color: Option<String>,
+        emoji: Option<String>,
+        birthdate: Option<NaiveDate>,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError> {
+        self.execute_audited(
+            "UPDATE kids SET color = ?1, emoji = ?2, birthdate = ?3 WHERE id = ?4",
+            libsql::params![
+                color,
+                emoji,
+                birthdate.map(|d| d.format("%Y-%m-%d").to_string()),
+                kid_id
+            ],
+            &audit,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn set_avatar_version(
+        &self,
+        kid_id: u32,
+        version: Option<i64>,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError> {
+        self.execute_audited(
+            "UPDATE kids SET avatar_version = ?1 WHERE id = ?2",
+            libsql::params![version, kid_id],
+            &audit,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn archive(&self, kid_id: u32, audit: NewAuditEntry) -> Result<(), ServerFnError> {
+        self.execute_audited(
+            "UPDATE kids SET archived_at = ?1 WHERE id = ?2 AND archived_at IS NULL",
+            libsql::params![timestamp(clock::now()), kid_id],
+            &audit,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn restore(
+        &self,
+        kid_id: u32,
+        check: impl FnOnce(&str, &[String]) -> Result<(), ValidationError>,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError> {
+        let tx = self.begin_write().await?;
+        let mut rows = tx
+            .query(
+                "SELECT name, archived_at IS NOT NULL FROM kids WHERE id = ?1",
+                libsql::params![kid_id],
+            )
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        let (name, archived): (String, bool) = match rows
+            .next()
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?
+        {
+            Some(row) => (
+                row.get(0).map_err(|e| ServerFnError::new(e.to_string()))?,
+                row.get(1).map_err(|e| ServerFnError::new(e.to_string()))?,
+            ),
+            None => return Err(ServerFnError::new("Kid not found".to_string())),
+        };
+        drop(rows);
+        if !archived {
+            return Ok(());
+        }
+        check(&name, &active_kid_names(&tx, None).await?)?;
+
+        tx.execute(
+            "UPDATE kids
+             SET archived_at = NULL,
+                 position = (SELECT COALESCE(MAX(position), 0) + 1 FROM kids)
+             WHERE id = ?1",
+            libsql::params![kid_id],
+        )
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+        append_entry(&tx, &audit).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn delete(
+        &self,
+        kid_id: u32,
+        check: impl FnOnce(bool) -> Result<(), ServerFnError>,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError> {
+        let tx = self.begin_write().await?;
+
+        let mut rows = tx
+            .query(
+                "SELECT archived_at IS NOT NULL FROM kids WHERE id = ?1",
+                libsql::params![kid_id],
+            )
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        let archived: bool = match rows
+            .next()
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?
+        {
+            Some(row) => row.get(0).map_err(|e| ServerFnError::new(e.to_string()))?,
+            None => return Err(ServerFnError::new("Kid not found".to_string())),
+        };
+        drop(rows);
+        check(archived)?;
+
+        for sql in [
+            "DELETE FROM notes WHERE kid_id = ?1",
+            "DELETE FROM kid_avatars WHERE kid_id = ?1",
+            "DELETE FROM kids WHERE id = ?1",
+        ] {
+            tx.execute(sql, libsql::params![kid_id])
+                .await
+                .map_err(|e| ServerFnError::new(e.to_string()))?;
+        }
+        append_entry(&tx, &audit).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        Ok(())
+    }
+}
+
+impl AvatarsRepository for LibsqlRepository {
+    async fn put_avatar(
+        &self,
+        kid_id: u32,
+        jpeg: Vec<u8>,
+        version: i64,
+        audit: NewAuditEntry,
+    ) -> Result<(), ServerFnError> {
+        let tx = self.begin_write().await?;
+        tx.execute(
+            "INSERT INTO kid_avatars (kid_id, image, updated_at)
+             VALUES (?1, ?2, ?3)
+             ON CONFLICT(kid_id) DO UPDATE SET image = excluded.image, updated_at = excluded.updated_at",
+            libsql::params![kid_id, jpeg, timestamp(clock::now())],
+        )
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+        tx.execute(
+            "UPDATE kids SET avatar_version = ?1 WHERE id = ?2",
+            libsql::params![version, kid_id],
+        )
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+        append_entry(&tx, &audit).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn avatar(&self, kid_id: u32) -> Result<Option<Vec<u8>>, ServerFnError> {
+        let conn = &self.conn;
+        let mut rows = conn
+            .query(
+                "SELECT image FROM kid_avatars WHERE kid_id = ?1",
+                libsql::params![kid_id],
+            )
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        match rows
+            .next()
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?
+        {
+            Some(row) => Ok(Some(
+                row.get::<Vec<u8>>(0)
+                    .map_err(|e| ServerFnError::new(e.to_string()))?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    async fn delete_avatar(&self, kid_id: u32, audit: NewAuditEntry) -> Result<(), ServerFnError> {
+        let tx = self.begin_write().await?;
+        for sql in [
+            "DELETE FROM kid_avatars WHERE kid_id = ?1",
+            "UPDATE kids SET avatar_version = NULL WHERE id = ?1",
+        ] {
+            tx.execute(sql, libsql::params![kid_id])
+                .await
+                .map_err(|e| ServerFnError::new(e.to_string()))?;
+        }
+        append_entry(&tx, &audit).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        Ok(())
+    }
+}
+
+impl NotesRepository for LibsqlRepository {
+    async fn record(
+        &self,
+        kid_id: u32,
+        quantity: i32,
+        idempotency_key: Option<&str>,
+        occurred_at: NaiveDateTime,
+        audit: NewAuditEntry,
+    ) -> Result<bool, ServerFnError> {
+        let tx = self.begin_write().await?;
+        let inserted = tx
+            .execute(
+                "INSERT INTO notes (kid_id, quantity, idempotency_key, occurred_at, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT(idempotency_key) DO NOTHING",
+                libsql::params![
+                    kid_id,
+                    quantity,
+                    idempotency_key,
+                    timestamp(occurred_at),
+                    timestamp(clock::now())
+                ],
+            )
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        if inserted == 0 {
+            return Ok(false);
+        }
+        append_entry(&tx, &audit).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        Ok(true)
+    }
+
+    async fn totals_between(
+        &self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<HashMap<u32, NoteTotals>, ServerFnError> {
+        let conn = &self.conn;
+        let mut rows = conn
+            .query(
+                "
+    SELECT kid_id, SUM(quantity) AS total, MAX(occurred_at) AS latest_note
+    FROM notes
+    WHERE occurred_at >= ?1 AND occurred_at < ?2
+    GROUP BY kid_id",
+                libsql::params![timestamp(start), timestamp(end)],
+            )
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+
+        let mut totals = HashMap::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?
+        {
+            let row =
+                de::from_row::<TotalsRow>(&row).map_err(|e| ServerFnError::new(e.to_string()))?;
+            totals.insert(
+                row.kid_id,
+                NoteTotals {
+                    total: row.total,
+                    latest_note: row.latest_note.as_deref().and_then(|s| {
+                        NaiveDateTime::parse_from_str(s, TIMESTAMP_FORMAT).ok()
+                    }),
+                },
+            );
+        }
+        Ok(totals)
+    }
+
+    async fn note_counts(&self) -> Result<HashMap<u32, NoteCounts>, ServerFnError> {
+        let conn = &self.conn;
+        let mut rows = conn
+            .query(
+                "
+    SELECT
+        kid_id,
+        SUM(CASE WHEN quantity > 0 THEN 1 ELSE 0 END) AS positive,
+        SUM(CASE WHEN quantity < 0 THEN 1 ELSE 0 END) AS negative
+    FROM notes
+    GROUP BY kid_id",
+                (),
+            )
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+
+        let mut counts = HashMap::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?
+        {
+            let row =
+                de::from_row::<CountsRow>(&row).map_err(|e| ServerFnError::new(e.to_string()))?;
+            counts.insert(
+                row.kid_id,
+                NoteCounts {
+                    positive: row.positive,
+                    negative: row.negative,
+                },
+            );
+        }
+        Ok(counts)
+    }
+
+    async fn activity(&self, utc_offset_minutes: i32) -> Result<Vec<HeatmapCell>, ServerFnError> {
+        let conn = &self.conn;
+        let mut rows = conn
+            .query(
+                "
+    SELECT
+        CAST(strftime('%w', occurred_at, ?1) AS INTEGER) AS weekday,
+        CAST(strftime('%H', occurred_at, ?1) AS INTEGER) AS hour,
+        COUNT(*) AS count
+    FROM notes
+    GROUP BY weekday, hour",
+                [format!("{utc_offset_minutes:+} minutes")],
+            )
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+
+        let mut cells = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?
+        {
+            let cell =
+                de::from_row::<HeatmapRow>(&row).map_err(|e| ServerFnError::new(e.to_string()))?;
+            cells.push(HeatmapCell {
+                // SQLite counts weekdays from Sunday
+                weekday: (cell.weekday + 6) % 7,
+                hour: cell.hour,
+                count: cell.count,
+            });
+        }
+        Ok(cells)
+    }
+
+    async fn all_notes(&self) -> Result<Vec<NoteRecord>, ServerFnError> {
+        let conn = &self.conn;
+        let rows = conn
+            .query(
+                "
+    SELECT kid_id, quantity, occurred_at, created_at, idempotency_key
+    FROM notes
+    ORDER BY occurred_at, id",
+                (),
+            )
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        note_records(rows).await
+    }
+
+    async fn kid_notes_between(
+        &self,
+        kid_id: u32,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<Vec<NoteRecord>, ServerFnError> {
+        let conn = &self.conn;
+        let rows = conn
+            .query(
+                "
+    SELECT kid_id, quantity, occurred_at, created_at, idempotency_key
+    FROM notes
+    WHERE kid_id = ?1 AND occurred_at >= ?2 AND occurred_at < ?3
+    ORDER BY occurred_at, id",
+                libsql::params![kid_id, timestamp(start), timestamp(end)],
+            )
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        note_records(rows).await
+    }
+}
+
+impl ImportRepository for LibsqlRepository {
+    async fn import(
+        &self,
+        batch: &ImportBatch,
+        commit: bool,
+        check: impl FnOnce(&[String]) -> Result<(), ValidationError>,
+        audit: impl FnOnce(&ImportCounts) -> NewAuditEntry,
+    ) -> Result<ImportCounts, ServerFnError> {
+        let tx = self.begin_write().await?;
+        check(&active_kid_names(&tx, None).await?)?;
+
+        if let Some(settings) = &batch.settings {
+            tx.execute(
+                "UPDATE settings
+                 SET granularity = ?1, leaderboard = ?2, max_kids = ?3, max_name_length = ?4,
+                     backdate_closed_cycles = ?5
+                 WHERE id = 1",
+                libsql::params![
+                    settings.granularity.as_str(),
+                    settings.leaderboard,
+                    settings.limits.max_kids,
+                    settings.limits.max_name_length,
+                    settings.backdate_closed_cycles
+                ],
+            )
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        }
+
+        let now = timestamp(clock::now());
+        let mut counts = ImportCounts::default();
+        let mut kid_ids = Vec::with_capacity(batch.kids.len());
+        for kid in &batch.kids {
+            match kid {
+                ImportedKid::Existing(id) => kid_ids.push(*id),
+                ImportedKid::New {
+                    name,
+                    color,
+                    emoji,
+                    birthdate,
+                    archived,
+                } => {
+                    let mut rows = tx
+                        .query(
+                            "INSERT INTO kids (name, position, color, emoji, birthdate, archived_at, created_at)
+                             VALUES (?1, (SELECT COALESCE(MAX(position), 0) + 1 FROM kids), ?2, ?3, ?4, ?5, ?6)
+                             RETURNING id",
+                            libsql::params![
+                                name.as_str(),
+                                color.clone(),
+                                emoji.clone(),
+                                birthdate.map(|d| d.format("%Y-%m-%d").to_string()),
+                                archived.then(|| now.clone()),
+                                now.as_str()
+                            ],
+                        )
+                        .await
+                        .map_err(|e| ServerFnError::new(e.to_string()))?;
+                    let id: u32 = match rows
+                        .next()
+                        .await
+                        .map_err(|e| ServerFnError::new(e.to_string()))?
+                    {
+                        Some(row) => row.get(0).map_err(|e| ServerFnError::new(e.to_string()))?,
+                        None => {
+                            return Err(ServerFnError::new(
+                                "Failed to retrieve inserted kid".to_string(),
+                            ))
+                        }
+                    };
+                    drop(rows);
+                    kid_ids.push(id);
+                    counts.kids_added += 1;
+                }
+            }
+        }
+
+        for note in &batch.notes {
+            let kid_id = *kid_ids
+                .get(note.kid)
+                .ok_or_else(|| ServerFnError::new("Kid not found".to_string()))?;
+            let inserted = tx
+                .execute(
+                    "INSERT INTO notes (kid_id, quantity, idempotency_key, occurred_at, created_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5)
+                     ON CONFLICT(idempotency_key) DO NOTHING",
+                    libsql::params![
+                        kid_id,
+                        note.quantity,
+                        note.idempotency_key.clone(),
+                        timestamp(note.occurred_at),
+                        timestamp(note.created_at)
+                    ],
+                )
+                .await
+                .map_err(|e| ServerFnError::new(e.to_string()))?;
+            if inserted > 0 {
+                counts.notes_added += 1;
+            } else {
+                counts.notes_skipped += 1;
+            }
+        }
+
+        if commit {
+            append_entry(&tx, &audit(&counts)).await?;
+            tx.commit().await
+        } else {
+            // A dry run. The transaction has its own connection (see `begin_write`),
+            // so this discards nothing another request wrote meanwhile
+            tx.rollback().await
+        }
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+        Ok(counts)
+    }
+}
+
+impl AuditRepository for LibsqlRepository {
+    async fn append(&self, entry: NewAuditEntry) -> Result<(), ServerFnError> {
+        append_entry(&self.conn, &entry).await
+    }
+
+    async fn audit_entries(
+        &self,
+        action: Option<AuditAction>,
+        start: Option<NaiveDateTime>,
+        end: Option<NaiveDateTime>,
+        limit: u32,
+    ) -> Result<Vec<AuditEntry>, ServerFnError> {
+        let conn = &self.conn;
+        let mut rows = conn
+            .query(
+                "
+    SELECT id, actor, action, kid_id, kid_name, before, after, created_at
+    FROM audit_log
+    WHERE (?1 IS NULL OR action = ?1)
+      AND (?2 IS NULL OR created_at >= ?2)
+      AND (?3 IS NULL OR created_at < ?3)
+    ORDER BY id DESC
+    LIMIT ?4",
+                libsql::params![
+                    action.map(|a| a.as_str()),
+                    start.map(timestamp),
+                    end.map(timestamp),
+                    limit
+                ],
+            )
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+
+        let mut entries = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?
+        {
+            let row =
+                de::from_row::<AuditRow>(&row).map_err(|e| ServerFnError::new(e.to_string()))?;
+            // Rows written by a newer version may carry actions this one doesn't know
+            let Some(action) = AuditAction::parse(&row.action) else {
+                continue;
+            };
+            entries.push(AuditEntry {
+                id: row.id,
+                actor: row.actor,
+                action,
+                kid_id: row.kid_id,
+                kid_name: row.kid_name,
+                before: row.before,
+                after: row.after,
+                created_at: NaiveDateTime::parse_from_str(&row.created_at, TIMESTAMP_FORMAT)
+                    .map_err(|e| ServerFnError::new(e.to_string()))?,
+            });
+        }
+        Ok(entries)
+    }
+}
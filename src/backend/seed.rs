@@ -0,0 +1,172 @@
+//! Demo data for developing the UI and for tests: a few kids with months of
+//! plausible notes. Generated from a seed, so the same options always produce the
+//! same data, and seeding twice adds nothing the second time.
+use crate::backend::audit;
+use crate::backend::clock;
+use crate::backend::events::publish;
+use crate::backend::repository::{
+    ImportBatch, ImportCounts, ImportRepository, ImportedKid, ImportedNote, KidsRepository,
+    SettingsRepository,
+};
+use crate::backend::validation::{ensure_room_for_kid, same_name};
+use crate::models::{AuditAction, ChangeEvent};
+use chrono::{Datelike, NaiveDate, TimeDelta, Weekday};
+use dioxus::prelude::ServerFnError;
+
+const NAMES: &[&str] = &[
+    "Ana", "Bia", "Caio", "Duda", "Enzo", "Flora", "Gael", "Helena", "Iago", "Julia", "Leo",
+    "Malu",
+];
+const COLORS: &[&str] = &["#ef4444", "#f59e0b", "#10b981", "#3b82f6", "#8b5cf6", "#ec4899"];
+const EMOJIS: &[&str] = &["🦊", "🐼", "🦁", "🐸", "🐙", "🦄"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SeedOptions {
+    /// At most `NAMES.len()`.
+    pub kids: u32,
+    /// Notes are spread over this many days up to today, so every granularity
+    /// gets several past cycles with the default of 400.
+    pub days: u32,
+    pub seed: u64,
+}
+
+impl Default for SeedOptions {
+    fn default() -> Self {
+        SeedOptions {
+            kids: 3,
+            days: 400,
+            seed: 1,
+        }
+    }
+}
+
+/// SplitMix64: tiny, and plenty random for demo data.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`.
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform in `[low, high)`.
+    fn range(&mut self, low: u32, high: u32) -> u32 {
+        low + (self.next() % (high - low) as u64) as u32
+    }
+}
+
+/// Adds `options.kids` demo kids, reusing ones that already have a demo name, and
+/// their notes for the last `options.days` days, in one transaction.
+///
+/// Each kid gets a temperament (how often notes are positive), more notes on
+/// weekends, and notes between 7:00 and 21:00. Notes carry idempotency keys derived
+/// from the seed.
+pub async fn seed_demo(
+    repo: &(impl KidsRepository + SettingsRepository + ImportRepository),
+    options: SeedOptions,
+) -> Result<ImportCounts, ServerFnError> {
+    if options.kids == 0 || options.kids as usize > NAMES.len() {
+        return Err(ServerFnError::new(format!(
+            "Can seed 1 to {} kids",
+            NAMES.len()
+        )));
+    }
+    let limits = repo.settings().await?.limits;
+    let existing = repo
+        .list_active()
+        .await?
+        .into_iter()
+        .chain(repo.list_archived().await?)
+        .collect::<Vec<_>>();
+
+    let mut rng = Rng(options.seed);
+    let mut batch = ImportBatch::default();
+    let mut new_kids = 0;
+    for (i, name) in NAMES.iter().take(options.kids as usize).enumerate() {
+        // Drawn for existing kids too, so the notes below come out the same on a rerun
+        let birthdate = NaiveDate::from_ymd_opt(
+            2014 + rng.range(0, 8) as i32,
+            rng.range(1, 13),
+            rng.range(1, 29),
+        );
+        match existing.iter().find(|k| same_name(&k.name, name)) {
+            Some(kid) => batch.kids.push(ImportedKid::Existing(kid.id)),
+            None => {
+                new_kids += 1;
+                batch.kids.push(ImportedKid::New {
+                    name: name.to_string(),
+                    color: Some(COLORS[i % COLORS.len()].to_string()),
+                    emoji: Some(EMOJIS[i % EMOJIS.len()].to_string()),
+                    birthdate,
+                    archived: false,
+                });
+            }
+        }
+    }
+
+    let now = clock::now();
+    let today = now.date();
+    let temperaments: Vec<f64> = (0..batch.kids.len())
+        .map(|_| 0.55 + rng.unit() * 0.3)
+        .collect();
+    for days_ago in (0..options.days).rev() {
+        let day = today - TimeDelta::days(days_ago as i64);
+        let weekend = matches!(day.weekday(), Weekday::Sat | Weekday::Sun);
+        for (kid, positive) in temperaments.iter().enumerate() {
+            let count = rng.range(0, if weekend { 5 } else { 3 });
+            for n in 0..count {
+                let minutes = rng.range(7 * 60, 21 * 60);
+                let occurred_at = day.and_hms_opt(minutes / 60, minutes % 60, 0).unwrap();
+                let quantity = if rng.unit() < *positive { 1 } else { -1 };
+                if occurred_at > now {
+                    continue;
+                }
+                batch.notes.push(ImportedNote {
+                    kid,
+                    quantity,
+                    occurred_at,
+                    created_at: occurred_at,
+                    idempotency_key: Some(format!(
+                        "seed-{}-{}-{kid}-{n}",
+                        options.seed,
+                        day.format("%Y%m%d")
+                    )),
+                });
+            }
+        }
+    }
+
+    let counts = repo
+        .import(
+            &batch,
+            true,
+            |active| {
+                for i in 0..new_kids {
+                    ensure_room_for_kid(active.len() as u32 + i, &limits)?;
+                }
+                Ok(())
+            },
+            |counts| {
+                audit::entry(
+                    AuditAction::DemoSeeded,
+                    None,
+                    None,
+                    Some(format!(
+                        "{} kids added, {} notes added",
+                        counts.kids_added, counts.notes_added
+                    )),
+                )
+            },
+        )
+        .await?;
+    publish(ChangeEvent::KidsChanged);
+    Ok(counts)
+}
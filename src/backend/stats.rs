@@ -0,0 +1,131 @@
+use crate::models::{CycleTotalsResponse, HeatmapCell, KidNoteRatio};
+use crate::models::KidCycleTotals;
+use chrono::{Datelike, Days, Months, NaiveDate, NaiveDateTime, NaiveTime};
+
+use crate::backend::clock;
+use crate::backend::kids::FORMAT_MAP;
+use crate::backend::repository::{KidsRepository, NotesRepository, SettingsRepository};
+use crate::backend::validation::validate_utc_offset;
+
+use dioxus::prelude::*;
+
+/// Upper bound for the number of cycles a chart can ask for.
+const MAX_CYCLES: u32 = 24;
+
+/// Returns the first day of the cycle containing `date`.
+fn cycle_start(granularity: &str, date: NaiveDate) -> NaiveDate {
+    match granularity {
+        "DAILY" => date,
+        "WEEKLY" => date - Days::new(date.weekday().num_days_from_monday() as u64),
+        "YEARLY" => NaiveDate::from_ymd_opt(date.year(), 1, 1).unwrap_or(date),
+        _ => date.with_day(1).unwrap_or(date),
+    }
+}
+
+/// Returns the half-open `[start, end)` range of the cycle containing `date`.
+pub(crate) fn cycle_bounds(
+    granularity: &str,
+    date: NaiveDate,
+) -> Result<(NaiveDateTime, NaiveDateTime), ServerFnError> {
+    let start = cycle_start(granularity, date);
+    let end = shift_cycles(granularity, start, 1, true)
+        .ok_or_else(|| ServerFnError::new("Cycle range out of bounds".to_string()))?;
+    Ok((start.and_time(NaiveTime::MIN), end.and_time(NaiveTime::MIN)))
+}
+
+/// Moves `date` (a cycle start) by `n` cycles backwards or forwards.
+pub(crate) fn shift_cycles(granularity: &str, date: NaiveDate, n: u32, forward: bool) -> Option<NaiveDate> {
+    match (granularity, forward) {
+        ("DAILY", true) => date.checked_add_days(Days::new(n as u64)),
+        ("DAILY", false) => date.checked_sub_days(Days::new(n as u64)),
+        ("WEEKLY", true) => date.checked_add_days(Days::new(7 * n as u64)),
+        ("WEEKLY", false) => date.checked_sub_days(Days::new(7 * n as u64)),
+        ("YEARLY", true) => date.checked_add_months(Months::new(12 * n)),
+        ("YEARLY", false) => date.checked_sub_months(Months::new(12 * n)),
+        (_, true) => date.checked_add_months(Months::new(n)),
+        (_, false) => date.checked_sub_months(Months::new(n)),
+    }
+}
+
+/// Fetches per-kid totals for each of the last `cycles` cycles (current one included),
+/// using the configured granularity. Cycles without notes are reported as zero.
+pub async fn get_cycle_totals(
+    repo: &(impl KidsRepository + NotesRepository + SettingsRepository),
+    cycles: u32,
+) -> Result<CycleTotalsResponse, ServerFnError> {
+    let cycles = cycles.clamp(1, MAX_CYCLES);
+    let granularity = repo.settings().await?.granularity;
+
+    let grain_format = *FORMAT_MAP
+        .get(granularity.as_str())
+        .ok_or_else(|| ServerFnError::new("Invalid granularity in settings".to_string()))?;
+
+    let today = clock::today();
+    let current = cycle_start(&granularity, today);
+    let first = shift_cycles(&granularity, current, cycles - 1, false)
+        .ok_or_else(|| ServerFnError::new("Cycle range out of bounds".to_string()))?;
+
+    let mut kids: Vec<KidCycleTotals> = repo
+        .list_active()
+        .await?
+        .into_iter()
+        .map(|kid| KidCycleTotals {
+            kid_id: kid.id,
+            name: kid.name,
+            color: kid.color,
+            totals: Vec::with_capacity(cycles as usize),
+        })
+        .collect();
+
+    let mut periods = Vec::with_capacity(cycles as usize);
+    let mut cursor = first;
+    for _ in 0..cycles {
+        let (start, end) = cycle_bounds(&granularity, cursor)?;
+        let totals = repo.totals_between(start, end).await?;
+        periods.push(cursor.format(grain_format).to_string());
+        for kid in kids.iter_mut() {
+            kid.totals.push(totals.get(&kid.kid_id).map_or(0, |t| t.total));
+        }
+        cursor = end.date();
+    }
+
+    Ok(CycleTotalsResponse {
+        granularity,
+        periods,
+        kids,
+    })
+}
+
+/// Fetches, for every kid, how many positive and negative notes were logged overall.
+pub async fn get_note_ratios(
+    repo: &(impl KidsRepository + NotesRepository),
+) -> Result<Vec<KidNoteRatio>, ServerFnError> {
+    let counts = repo.note_counts().await?;
+    let ratios = repo
+        .list_active()
+        .await?
+        .into_iter()
+        .map(|kid| {
+            let counts = counts.get(&kid.id).copied().unwrap_or_default();
+            KidNoteRatio {
+                kid_id: kid.id,
+                name: kid.name,
+                color: kid.color,
+                positive: counts.positive,
+                negative: counts.negative,
+            }
+        })
+        .collect();
+    Ok(ratios)
+}
+
+/// Fetches how many notes were logged per weekday (0 = Monday) and hour of day, in
+/// the client's time zone given as minutes ahead of UTC. Only non-empty cells are
+/// returned.
+pub async fn get_activity_heatmap(
+    repo: &impl NotesRepository,
+    utc_offset_minutes: i32,
+) -> Result<Vec<HeatmapCell>, ServerFnError> {
+    validate_utc_offset(utc_offset_minutes)?;
+    repo.activity(utc_offset_minutes).await
+}
@@ -0,0 +1,1016 @@
+//! Runs the backend functions against a throwaway local libSQL database with the
+//! clock pinned, and a few of them against the in-memory store too. Needs a backend
+//! build: `cargo test --features server`.
+use crate::backend::clock::set_now;
+use crate::backend::{audit, avatars, backup, export, import, kids, report, seed, stats};
+use crate::backend::migrations::{run_migrations, schema_version};
+use crate::backend::repository::{AvatarsRepository, InMemoryRepository, LibsqlRepository};
+use crate::models::{
+    AuditAction, CountAggregation, ExportFormat, KidLimits, ReportFormat, ReportPeriod,
+    MAX_IMPORT_BYTES,
+};
+use chrono::NaiveDateTime;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// A migrated database in a temporary file, removed on drop.
+struct TestDb {
+    repo: LibsqlRepository,
+    /// Raw access to the same database, for checks the repository doesn't offer.
+    conn: libsql::Connection,
+    path: PathBuf,
+}
+
+impl TestDb {
+    async fn new() -> Self {
+        let path = std::env::temp_dir().join(format!("hot_dog-test-{}.db", uuid::Uuid::new_v4()));
+        let db = libsql::Builder::new_local(&path)
+            .build()
+            .await
+            .expect("Failed to build test database");
+        let conn = db.connect().expect("Failed to connect to test database");
+        run_migrations(&conn)
+            .await
+            .expect("Failed to apply migrations");
+        TestDb {
+            repo: LibsqlRepository::new(Arc::new(db), conn.clone()),
+            conn,
+            path,
+        }
+    }
+}
+
+impl Drop for TestDb {
+    fn drop(&mut self) {
+        set_now(None);
+        for suffix in ["", "-wal", "-shm"] {
+            let mut path = self.path.clone().into_os_string();
+            path.push(suffix);
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+fn at(timestamp: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S").expect("Bad test timestamp")
+}
+
+async fn note_at(db: &TestDb, kid_id: u32, add: bool, timestamp: &str) {
+    set_now(Some(at(timestamp)));
+    kids::log_note(&db.repo, kid_id, add, None, None)
+        .await
+        .expect("Failed to log note");
+}
+
+async fn count_at(db: &TestDb, kid_id: u32, timestamp: &str) -> (i32, CountAggregation) {
+    set_now(Some(at(timestamp)));
+    let response = kids::get_kids(&db.repo, None).await.expect("Failed to get kids");
+    let kid = response
+        .kids
+        .iter()
+        .find(|k| k.id == kid_id)
+        .expect("Kid missing from get_kids");
+    (kid.count, response.count_metadata.aggregation)
+}
+
+#[tokio::test]
+async fn migrations_apply_once() {
+    let db = TestDb::new().await;
+    let version = schema_version(&db.conn).await.unwrap();
+    assert!(version > 0);
+
+    run_migrations(&db.conn).await.unwrap();
+    assert_eq!(schema_version(&db.conn).await.unwrap(), version);
+    let mut rows = db
+        .conn
+        .query("SELECT COUNT(*) FROM schema_migrations", ())
+        .await
+        .unwrap();
+    let applied: u32 = rows.next().await.unwrap().unwrap().get(0).unwrap();
+    assert_eq!(applied, version);
+}
+
+#[tokio::test]
+async fn add_kid_appends_kids_in_order() {
+    let db = TestDb::new().await;
+
+    let ana = kids::add_kid(&db.repo, "  Ana  ".to_string()).await.unwrap();
+    let bia = kids::add_kid(&db.repo, "Bia".to_string()).await.unwrap();
+    assert_eq!(ana.name, "Ana");
+    assert!(ana.position < bia.position);
+
+    let names: Vec<String> = kids::list_kids(&db.repo)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|k| k.name)
+        .collect();
+    assert_eq!(names, ["Ana", "Bia"]);
+}
+
+#[tokio::test]
+async fn reorder_kids_must_list_every_active_kid_once() {
+    let db = TestDb::new().await;
+    let ana = kids::add_kid(&db.repo, "Ana".to_string()).await.unwrap();
+    let bia = kids::add_kid(&db.repo, "Bia".to_string()).await.unwrap();
+    let caio = kids::add_kid(&db.repo, "Caio".to_string()).await.unwrap();
+    kids::archive_kid(&db.repo, caio.id).await.unwrap();
+
+    for kid_ids in [
+        vec![ana.id, ana.id],          // duplicate
+        vec![bia.id],                  // missing
+        vec![bia.id, ana.id, 999],     // unknown
+        vec![bia.id, ana.id, caio.id], // archived
+    ] {
+        let err = kids::reorder_kids(&db.repo, kid_ids).await.unwrap_err();
+        assert!(err.to_string().contains("every kid exactly once"));
+    }
+
+    kids::reorder_kids(&db.repo, vec![bia.id, ana.id]).await.unwrap();
+    let names: Vec<String> = kids::list_kids(&db.repo)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|k| k.name)
+        .collect();
+    assert_eq!(names, ["Bia", "Ana"]);
+}
+
+#[tokio::test]
+async fn add_kid_rejects_duplicates_and_enforces_the_limit() {
+    let db = TestDb::new().await;
+    kids::update_kid_limits(
+        &db.repo,
+        KidLimits {
+            max_kids: 2,
+            ..KidLimits::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    kids::add_kid(&db.repo, "Ana".to_string()).await.unwrap();
+    assert!(kids::add_kid(&db.repo, "ana".to_string()).await.is_err());
+    assert!(kids::add_kid(&db.repo, " ".to_string()).await.is_err());
+
+    kids::add_kid(&db.repo, "Bia".to_string()).await.unwrap();
+    assert!(kids::add_kid(&db.repo, "Caio".to_string()).await.is_err());
+    assert_eq!(kids::list_kids(&db.repo).await.unwrap().len(), 2);
+}
+
+// Spawned on a multi-threaded runtime so the two adds really do run at once
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn concurrent_adds_respect_the_kid_limit() {
+    let db = Arc::new(TestDb::new().await);
+    kids::update_kid_limits(
+        &db.repo,
+        KidLimits {
+            max_kids: 1,
+            ..KidLimits::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let add = |name: &str| {
+        let (db, name) = (db.clone(), name.to_string());
+        tokio::spawn(async move { kids::add_kid(&db.repo, name).await })
+    };
+    let (ana, bia) = (add("Ana"), add("Bia"));
+    let (ana, bia) = (ana.await.unwrap(), bia.await.unwrap());
+    assert!(ana.is_ok() != bia.is_ok());
+    assert_eq!(kids::list_kids(&db.repo).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn archiving_twice_is_audited_once() {
+    let db = TestDb::new().await;
+    let ana = kids::add_kid(&db.repo, "Ana".to_string()).await.unwrap();
+
+    for _ in 0..2 {
+        kids::archive_kid(&db.repo, ana.id).await.unwrap();
+    }
+    for _ in 0..2 {
+        kids::restore_kid(&db.repo, ana.id).await.unwrap();
+    }
+
+    for action in [AuditAction::KidArchived, AuditAction::KidRestored] {
+        let entries = audit::list_audit_log(&db.repo, Some(action), None, None)
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+    }
+}
+
+#[tokio::test]
+async fn rename_kid_keeps_names_unique() {
+    let db = TestDb::new().await;
+    let ana = kids::add_kid(&db.repo, "Ana".to_string()).await.unwrap();
+    kids::add_kid(&db.repo, "Bia".to_string()).await.unwrap();
+
+    assert!(kids::rename_kid(&db.repo, ana.id, "BIA".to_string())
+        .await
+        .is_err());
+    // Changing only the case of its own name is fine
+    kids::rename_kid(&db.repo, ana.id, "ANA".to_string())
+        .await
+        .unwrap();
+    kids::rename_kid(&db.repo, ana.id, "Anita".to_string())
+        .await
+        .unwrap();
+
+    let kids = kids::list_kids(&db.repo).await.unwrap();
+    assert_eq!(kids[0].name, "Anita");
+    assert!(kids::rename_kid(&db.repo, 999, "Caio".to_string())
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn delete_kid_requires_archiving_first() {
+    let db = TestDb::new().await;
+    let ana = kids::add_kid(&db.repo, "Ana".to_string()).await.unwrap();
+    note_at(&db, ana.id, true, "2026-03-10 08:00:00").await;
+
+    assert!(kids::delete_kid(&db.repo, ana.id).await.is_err());
+
+    kids::archive_kid(&db.repo, ana.id).await.unwrap();
+    kids::delete_kid(&db.repo, ana.id).await.unwrap();
+    assert!(kids::list_kids(&db.repo).await.unwrap().is_empty());
+    assert!(kids::list_archived_kids(&db.repo).await.unwrap().is_empty());
+
+    // A new kid doesn't inherit anything from the deleted one
+    let bia = kids::add_kid(&db.repo, "Bia".to_string()).await.unwrap();
+    assert_eq!(count_at(&db, bia.id, "2026-03-10 09:00:00").await.0, 0);
+}
+
+#[tokio::test]
+async fn log_note_records_a_retried_note_once() {
+    let db = TestDb::new().await;
+    let ana = kids::add_kid(&db.repo, "Ana".to_string()).await.unwrap();
+    set_now(Some(at("2026-03-10 08:00:00")));
+
+    for _ in 0..3 {
+        kids::log_note(&db.repo, ana.id, true, Some("tap-1".to_string()), None)
+            .await
+            .unwrap();
+    }
+    kids::log_note(&db.repo, ana.id, false, Some("tap-2".to_string()), None)
+        .await
+        .unwrap();
+    kids::log_note(&db.repo, ana.id, true, None, None).await.unwrap();
+    assert!(kids::log_note(&db.repo, ana.id, true, Some(String::new()), None)
+        .await
+        .is_err());
+
+    assert_eq!(count_at(&db, ana.id, "2026-03-10 09:00:00").await.0, 1);
+}
+
+#[tokio::test]
+async fn archived_kids_take_no_notes() {
+    let db = TestDb::new().await;
+    let ana = kids::add_kid(&db.repo, "Ana".to_string()).await.unwrap();
+    note_at(&db, ana.id, true, "2026-03-10 08:00:00").await;
+
+    kids::archive_kid(&db.repo, ana.id).await.unwrap();
+    assert!(kids::log_note(&db.repo, ana.id, true, None, None)
+        .await
+        .is_err());
+    assert!(kids::log_note(&db.repo, 999, true, None, None).await.is_err());
+
+    kids::restore_kid(&db.repo, ana.id).await.unwrap();
+    assert_eq!(count_at(&db, ana.id, "2026-03-10 09:00:00").await.0, 1);
+}
+
+#[tokio::test]
+async fn update_granularity_rejects_unknown_values() {
+    let db = TestDb::new().await;
+    assert!(kids::update_granularity(&db.repo, "HOURLY".to_string())
+        .await
+        .is_err());
+    assert_eq!(kids::get_granularity(&db.repo).await.unwrap(), "MONTHLY");
+}
+
+/// Per granularity: a note just before the current cycle, one at its very start,
+/// and the moment the count is read.
+#[tokio::test]
+async fn get_kids_counts_the_current_cycle_for_every_granularity() {
+    let cases = [
+        (
+            "DAILY",
+            "2026-03-09 23:59:59",
+            "2026-03-10 00:00:00",
+            "2026-03-10 21:00:00",
+            CountAggregation::Daily(10, 3, 2026),
+        ),
+        (
+            // 2026-03-09 is a Monday
+            "WEEKLY",
+            "2026-03-08 23:59:59",
+            "2026-03-09 00:00:00",
+            "2026-03-15 23:00:00",
+            CountAggregation::Weekly(11, 3, 2026),
+        ),
+        (
+            "MONTHLY",
+            "2026-02-28 23:59:59",
+            "2026-03-01 00:00:00",
+            "2026-03-31 23:00:00",
+            CountAggregation::Monthly(3, 2026),
+        ),
+        (
+            "YEARLY",
+            "2025-12-31 23:59:59",
+            "2026-01-01 00:00:00",
+            "2026-06-01 12:00:00",
+            CountAggregation::Yearly(2026),
+        ),
+    ];
+
+    for (granularity, before, start, read_at, aggregation) in cases {
+        let db = TestDb::new().await;
+        kids::update_granularity(&db.repo, granularity.to_string())
+            .await
+            .unwrap();
+        let ana = kids::add_kid(&db.repo, "Ana".to_string()).await.unwrap();
+
+        note_at(&db, ana.id, true, before).await;
+        note_at(&db, ana.id, true, start).await;
+        note_at(&db, ana.id, true, start).await;
+        note_at(&db, ana.id, false, start).await;
+
+        let (count, current) = count_at(&db, ana.id, read_at).await;
+        assert_eq!(count, 1, "{granularity}");
+        assert_eq!(current, aggregation, "{granularity}");
+    }
+}
+
+#[tokio::test]
+async fn get_kids_starts_from_zero_when_the_cycle_rolls_over() {
+    let db = TestDb::new().await;
+    let ana = kids::add_kid(&db.repo, "Ana".to_string()).await.unwrap();
+    note_at(&db, ana.id, true, "2026-03-31 23:59:59").await;
+
+    assert_eq!(count_at(&db, ana.id, "2026-03-31 23:59:59").await.0, 1);
+    assert_eq!(count_at(&db, ana.id, "2026-04-01 00:00:00").await.0, 0);
+
+    // Switching granularity regroups the same notes
+    kids::update_granularity(&db.repo, "YEARLY".to_string())
+        .await
+        .unwrap();
+    assert_eq!(count_at(&db, ana.id, "2026-04-01 00:00:00").await.0, 1);
+}
+
+#[tokio::test]
+async fn get_cycle_totals_splits_notes_on_cycle_boundaries() {
+    let db = TestDb::new().await;
+    let ana = kids::add_kid(&db.repo, "Ana".to_string()).await.unwrap();
+    kids::update_granularity(&db.repo, "WEEKLY".to_string())
+        .await
+        .unwrap();
+
+    // Weeks starting Monday 2026-03-02, 2026-03-09 and 2026-03-16
+    note_at(&db, ana.id, true, "2026-03-01 23:59:59").await;
+    note_at(&db, ana.id, true, "2026-03-02 00:00:00").await;
+    note_at(&db, ana.id, true, "2026-03-08 23:59:59").await;
+    note_at(&db, ana.id, false, "2026-03-09 00:00:00").await;
+    note_at(&db, ana.id, true, "2026-03-16 00:00:00").await;
+
+    set_now(Some(at("2026-03-18 12:00:00")));
+    let response = stats::get_cycle_totals(&db.repo, 3).await.unwrap();
+    assert_eq!(response.periods, ["2026-W09", "2026-W10", "2026-W11"]);
+    assert_eq!(response.kids[0].totals, [2, -1, 1]);
+}
+
+#[tokio::test]
+async fn activity_heatmap_uses_the_clients_time_zone() {
+    let db = TestDb::new().await;
+    let ana = kids::add_kid(&db.repo, "Ana".to_string()).await.unwrap();
+    // Sunday night in UTC, Monday morning at UTC+3 and Sunday afternoon at UTC-3
+    note_at(&db, ana.id, true, "2026-03-01 23:30:00").await;
+
+    let cells = stats::get_activity_heatmap(&db.repo, 0).await.unwrap();
+    assert_eq!((cells[0].weekday, cells[0].hour), (6, 23));
+    let cells = stats::get_activity_heatmap(&db.repo, 180).await.unwrap();
+    assert_eq!((cells[0].weekday, cells[0].hour), (0, 2));
+    let cells = stats::get_activity_heatmap(&db.repo, -180).await.unwrap();
+    assert_eq!((cells[0].weekday, cells[0].hour), (6, 20));
+    assert!(stats::get_activity_heatmap(&db.repo, 24 * 60).await.is_err());
+}
+
+#[tokio::test]
+async fn get_kids_shows_past_cycles_as_closed() {
+    let db = TestDb::new().await;
+    let ana = kids::add_kid(&db.repo, "Ana".to_string()).await.unwrap();
+    note_at(&db, ana.id, true, "2026-02-14 10:00:00").await;
+    note_at(&db, ana.id, true, "2026-02-28 23:59:59").await;
+    note_at(&db, ana.id, false, "2026-03-01 00:00:00").await;
+
+    set_now(Some(at("2026-03-10 12:00:00")));
+    let february = chrono::NaiveDate::from_ymd_opt(2026, 2, 1);
+    let past = kids::get_kids(&db.repo, february).await.unwrap();
+    assert_eq!(past.kids[0].count, 2);
+    assert_eq!(past.count_metadata.aggregation, CountAggregation::Monthly(2, 2026));
+    assert!(!past.count_metadata.current);
+    assert_eq!(past.count_metadata.period_end, chrono::NaiveDate::from_ymd_opt(2026, 3, 1).unwrap());
+    // Today comes from the pinned clock, whichever cycle is shown
+    assert_eq!(past.count_metadata.today, chrono::NaiveDate::from_ymd_opt(2026, 3, 10).unwrap());
+
+    let current = kids::get_kids(&db.repo, None).await.unwrap();
+    assert_eq!(current.kids[0].count, -1);
+    assert!(current.count_metadata.current);
+
+    let april = chrono::NaiveDate::from_ymd_opt(2026, 4, 1);
+    assert!(kids::get_kids(&db.repo, april).await.is_err());
+}
+
+#[tokio::test]
+async fn backdated_notes_count_towards_the_cycle_they_happened_in() {
+    let db = TestDb::new().await;
+    let ana = kids::add_kid(&db.repo, "Ana".to_string()).await.unwrap();
+    set_now(Some(at("2026-03-03 20:00:00")));
+    let backdate = |timestamp: &str| Some(at(timestamp));
+
+    // Same cycle, two days back
+    kids::log_note(&db.repo, ana.id, true, None, backdate("2026-03-01 08:00:00"))
+        .await
+        .unwrap();
+    // Future, too old, and into the closed February cycle are all refused
+    for timestamp in ["2026-03-03 21:00:00", "2026-02-20 08:00:00", "2026-02-27 08:00:00"] {
+        assert!(kids::log_note(&db.repo, ana.id, true, None, backdate(timestamp))
+            .await
+            .is_err());
+    }
+
+    kids::update_backdate_closed_cycles(&db.repo, true)
+        .await
+        .unwrap();
+    kids::log_note(&db.repo, ana.id, true, None, backdate("2026-02-27 08:00:00"))
+        .await
+        .unwrap();
+
+    let march = kids::get_kids(&db.repo, None).await.unwrap();
+    assert_eq!(march.kids[0].count, 1);
+    assert_eq!(march.kids[0].latest_note, backdate("2026-03-01 08:00:00"));
+    let february = kids::get_kids(&db.repo, chrono::NaiveDate::from_ymd_opt(2026, 2, 1))
+        .await
+        .unwrap();
+    assert_eq!(february.kids[0].count, 1);
+}
+
+#[tokio::test]
+async fn backdated_taps_are_read_in_the_clients_time_zone() {
+    let db = TestDb::new().await;
+    let ana = kids::add_kid(&db.repo, "Ana".to_string()).await.unwrap();
+    set_now(Some(at("2026-03-01 01:00:00")));
+    let backdate = |timestamp: &str| Some(at(timestamp));
+
+    // Ten minutes ago for a parent at UTC+2, not in the future
+    kids::increment_kid_count(&db.repo, ana.id, None, backdate("2026-03-01 02:50:00"), 120)
+        .await
+        .unwrap();
+    // Still 28 February at UTC-3, but already March in UTC
+    kids::increment_kid_count(&db.repo, ana.id, None, backdate("2026-02-28 21:30:00"), -180)
+        .await
+        .unwrap();
+    // No time zone is 15 hours ahead
+    let tap = backdate("2026-03-01 02:50:00");
+    assert!(kids::increment_kid_count(&db.repo, ana.id, None, tap, 15 * 60)
+        .await
+        .is_err());
+
+    let march = kids::get_kids(&db.repo, None).await.unwrap();
+    assert_eq!(march.kids[0].count, 2);
+    assert_eq!(march.kids[0].latest_note, backdate("2026-03-01 00:50:00"));
+}
+
+#[tokio::test]
+async fn queued_notes_keep_the_time_of_the_tap() {
+    let db = TestDb::new().await;
+    let ana = kids::add_kid(&db.repo, "Ana".to_string()).await.unwrap();
+    set_now(Some(at("2026-03-03 20:00:00")));
+    let tapped = |timestamp: &str| Some(at(timestamp));
+
+    // Replayed an hour after the tap
+    kids::log_note(&db.repo, ana.id, true, Some("tap-1".to_string()), tapped("2026-03-03 19:00:00"))
+        .await
+        .unwrap();
+    // From a device whose clock runs two minutes ahead
+    kids::log_note(&db.repo, ana.id, true, Some("tap-2".to_string()), tapped("2026-03-03 20:02:00"))
+        .await
+        .unwrap();
+    assert!(kids::log_note(&db.repo, ana.id, true, None, tapped("2026-03-03 20:30:00"))
+        .await
+        .is_err());
+
+    let march = kids::get_kids(&db.repo, None).await.unwrap();
+    assert_eq!(march.kids[0].count, 2);
+    assert_eq!(march.kids[0].latest_note, tapped("2026-03-03 20:00:00"));
+}
+
+#[tokio::test]
+async fn audit_log_keeps_changes_with_their_before_and_after() {
+    let db = TestDb::new().await;
+    set_now(Some(at("2026-03-10 08:00:00")));
+    let ana = kids::add_kid(&db.repo, "Ana".to_string()).await.unwrap();
+    kids::rename_kid(&db.repo, ana.id, "Anna".to_string())
+        .await
+        .unwrap();
+    for _ in 0..2 {
+        kids::log_note(&db.repo, ana.id, true, Some("tap-1".to_string()), None)
+            .await
+            .unwrap();
+    }
+    // Refused changes leave no entry behind
+    assert!(kids::delete_kid(&db.repo, ana.id).await.is_err());
+    set_now(Some(at("2026-03-12 08:00:00")));
+    kids::update_granularity(&db.repo, "WEEKLY".to_string())
+        .await
+        .unwrap();
+
+    let all = audit::list_audit_log(&db.repo, None, None, None).await.unwrap();
+    let actions: Vec<_> = all.iter().map(|e| e.action).collect();
+    assert_eq!(
+        actions,
+        [
+            AuditAction::GranularityChanged,
+            AuditAction::NoteLogged,
+            AuditAction::KidRenamed,
+            AuditAction::KidAdded,
+        ]
+    );
+    assert_eq!(all[2].before.as_deref(), Some("Ana"));
+    assert_eq!(all[2].after.as_deref(), Some("Anna"));
+    assert_eq!(all[1].kid_name.as_deref(), Some("Anna"));
+
+    let renames = audit::list_audit_log(&db.repo, Some(AuditAction::KidRenamed), None, None)
+        .await
+        .unwrap();
+    assert_eq!(renames.len(), 1);
+    let day = chrono::NaiveDate::from_ymd_opt(2026, 3, 12);
+    let on_the_12th = audit::list_audit_log(&db.repo, None, day, day).await.unwrap();
+    assert_eq!(on_the_12th.len(), 1);
+    assert_eq!(on_the_12th[0].before.as_deref(), Some("MONTHLY"));
+
+    // The table itself refuses edits
+    let conn = &db.conn;
+    assert!(conn.execute("DELETE FROM audit_log", ()).await.is_err());
+    assert!(conn
+        .execute("UPDATE audit_log SET actor = 'someone'", ())
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn export_includes_archived_kids_and_totals_per_cycle() {
+    let db = TestDb::new().await;
+    let ana = kids::add_kid(&db.repo, "Ana".to_string()).await.unwrap();
+    let bia = kids::add_kid(&db.repo, "Bia".to_string()).await.unwrap();
+    note_at(&db, ana.id, true, "2026-02-27 08:00:00").await;
+    note_at(&db, ana.id, true, "2026-03-02 08:00:00").await;
+    note_at(&db, ana.id, false, "2026-03-05 08:00:00").await;
+    note_at(&db, bia.id, true, "2026-03-06 08:00:00").await;
+    kids::archive_kid(&db.repo, bia.id).await.unwrap();
+
+    let export = export::build_export(&db.repo).await.unwrap();
+    assert_eq!(export.settings.granularity, "MONTHLY");
+    assert_eq!(
+        export.kids.iter().map(|k| (k.name.as_str(), k.archived)).collect::<Vec<_>>(),
+        [("Ana", false), ("Bia", true)]
+    );
+    assert_eq!(export.notes.len(), 4);
+    assert_eq!(export.notes[0].occurred_at, at("2026-02-27 08:00:00"));
+
+    let cycles: Vec<_> = export
+        .cycles
+        .iter()
+        .map(|c| (c.period.as_str(), c.kid_id, c.total))
+        .collect();
+    assert_eq!(
+        cycles,
+        [("2026-02", ana.id, 1), ("2026-03", ana.id, 0), ("2026-03", bia.id, 1)]
+    );
+}
+
+#[tokio::test]
+async fn import_previews_then_loads_an_export_once() {
+    let source = TestDb::new().await;
+    let ana = kids::add_kid(&source.repo, "Ana".to_string()).await.unwrap();
+    let bia = kids::add_kid(&source.repo, "Bia".to_string()).await.unwrap();
+    for (kid_id, timestamp) in [(ana.id, "2026-02-27 08:00:00"), (bia.id, "2026-03-02 08:00:00")] {
+        set_now(Some(at(timestamp)));
+        kids::log_note(&source.repo, kid_id, true, Some(format!("tap-{kid_id}")), None)
+            .await
+            .unwrap();
+    }
+    let file = export::export_data(&source.repo, ExportFormat::CsvZip)
+        .await
+        .unwrap();
+
+    let target = TestDb::new().await;
+    kids::add_kid(&target.repo, "ana".to_string()).await.unwrap();
+    set_now(Some(at("2026-03-10 08:00:00")));
+
+    let preview = import::import_data(&target.repo, file.bytes.clone(), true)
+        .await
+        .unwrap();
+    assert!(preview.errors.is_empty(), "{:?}", preview.errors);
+    assert_eq!(preview.kids_matched, ["ana"]);
+    assert_eq!(preview.kids_added, ["Bia"]);
+    assert_eq!(preview.notes_added, 2);
+    assert!(!preview.committed);
+    assert_eq!(kids::list_kids(&target.repo).await.unwrap().len(), 1);
+
+    let imported = import::import_data(&target.repo, file.bytes.clone(), false)
+        .await
+        .unwrap();
+    assert!(imported.committed);
+    assert_eq!(count_at(&target, bia_id(&target).await, "2026-03-10 09:00:00").await.0, 1);
+
+    // The notes keep their idempotency keys, so importing again adds nothing
+    let again = import::import_data(&target.repo, file.bytes, false).await.unwrap();
+    assert_eq!((again.notes_added, again.notes_skipped), (0, 2));
+}
+
+#[tokio::test]
+async fn notes_without_keys_are_imported_once() {
+    let db = TestDb::new().await;
+    set_now(Some(at("2026-03-10 08:00:00")));
+    let json = r#"{
+        "kids": [{ "id": 1, "name": "Ana" }],
+        "notes": [
+            { "kid_id": 1, "quantity": 1, "occurred_at": "2026-03-01T08:00:00" },
+            { "kid_id": 1, "quantity": 1, "occurred_at": "2026-03-01T08:00:00" },
+            { "kid_id": 1, "quantity": -1, "occurred_at": "2026-03-02T08:00:00" }
+        ]
+    }"#;
+
+    let first = import::import_data(&db.repo, json.as_bytes().to_vec(), false)
+        .await
+        .unwrap();
+    assert_eq!((first.notes_added, first.notes_skipped), (3, 0));
+    let again = import::import_data(&db.repo, json.as_bytes().to_vec(), false)
+        .await
+        .unwrap();
+    assert_eq!((again.notes_added, again.notes_skipped), (0, 3));
+}
+
+#[tokio::test]
+async fn notes_from_before_keys_are_not_imported_twice() {
+    let db = TestDb::new().await;
+    let ana = kids::add_kid(&db.repo, "Ana".to_string()).await.unwrap();
+    note_at(&db, ana.id, true, "2026-03-02 08:00:00").await;
+    // Logged before notes had keys: the backfill runs again over it
+    db.conn
+        .execute(
+            "INSERT INTO notes (kid_id, quantity, occurred_at, created_at)
+             VALUES (?1, -1, ?2, ?2)",
+            libsql::params![ana.id, "2026-03-01 08:00:00"],
+        )
+        .await
+        .unwrap();
+    db.conn
+        .execute("DELETE FROM schema_migrations WHERE version = 10", ())
+        .await
+        .unwrap();
+    run_migrations(&db.conn).await.unwrap();
+
+    set_now(Some(at("2026-03-10 08:00:00")));
+    let file = export::export_data(&db.repo, ExportFormat::Json)
+        .await
+        .unwrap();
+    let again = import::import_data(&db.repo, file.bytes, false).await.unwrap();
+    assert!(again.errors.is_empty(), "{:?}", again.errors);
+    assert_eq!((again.notes_added, again.notes_skipped), (0, 2));
+}
+
+async fn bia_id(db: &TestDb) -> u32 {
+    kids::list_kids(&db.repo)
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|k| k.name == "Bia")
+        .unwrap()
+        .id
+}
+
+// The clock is pinned per thread, so this one runs on the real clock: its tasks
+// may move between worker threads
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn dry_run_imports_keep_notes_logged_meanwhile() {
+    let db = Arc::new(TestDb::new().await);
+    let ana = kids::add_kid(&db.repo, "Ana".to_string()).await.unwrap();
+    let json = r#"{
+        "kids": [{ "id": 1, "name": "Bia" }],
+        "notes": [{ "kid_id": 1, "quantity": 1, "occurred_at": "2026-03-01T08:00:00" }]
+    }"#;
+
+    let preview = tokio::spawn({
+        let db = db.clone();
+        async move { import::import_data(&db.repo, json.as_bytes().to_vec(), true).await }
+    });
+    let logged = tokio::spawn({
+        let db = db.clone();
+        async move {
+            kids::log_note(&db.repo, ana.id, true, Some("tap-1".to_string()), None).await
+        }
+    });
+    assert!(!preview.await.unwrap().unwrap().committed);
+    logged.await.unwrap().unwrap();
+    assert_eq!(kids::list_kids(&db.repo).await.unwrap().len(), 1);
+    let notes = audit::list_audit_log(&db.repo, Some(AuditAction::NoteLogged), None, None)
+        .await
+        .unwrap();
+    assert_eq!(notes.len(), 1);
+}
+
+#[tokio::test]
+async fn import_lists_problems_and_writes_nothing() {
+    let db = TestDb::new().await;
+    set_now(Some(at("2026-03-10 08:00:00")));
+    let json = r#"{
+        "kids": [{ "id": 1, "name": "Ana" }],
+        "notes": [
+            { "kid_id": 1, "quantity": 1, "occurred_at": "2026-03-01T08:00:00" },
+            { "kid_id": 2, "quantity": 1, "occurred_at": "2026-03-01T08:00:00" },
+            { "kid_id": 1, "quantity": 5, "occurred_at": "2026-03-01T08:00:00" },
+            { "kid_id": 1, "quantity": -1, "occurred_at": "2026-04-01T08:00:00" }
+        ]
+    }"#;
+
+    let preview = import::import_data(&db.repo, json.as_bytes().to_vec(), false)
+        .await
+        .unwrap();
+    assert_eq!(preview.errors.len(), 3, "{:?}", preview.errors);
+    assert!(!preview.committed);
+    assert!(kids::list_kids(&db.repo).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn import_refuses_zips_that_unzip_too_large() {
+    use std::io::Write;
+
+    let db = TestDb::new().await;
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    zip.start_file("kids.csv", options).unwrap();
+    zip.write_all(b"id,name\n1,Ana\n").unwrap();
+    zip.start_file("notes.csv", options).unwrap();
+    zip.write_all(b"kid_id,quantity,occurred_at\n").unwrap();
+    let row = b"1,1,2026-03-01T08:00:00\n";
+    for _ in 0..=MAX_IMPORT_BYTES / row.len() {
+        zip.write_all(row).unwrap();
+    }
+    let bytes = zip.finish().unwrap().into_inner();
+    assert!(bytes.len() < MAX_IMPORT_BYTES);
+
+    let preview = import::import_data(&db.repo, bytes, true).await.unwrap();
+    assert_eq!(preview.errors, ["notes.csv: larger than 20 MB unzipped"]);
+}
+
+#[tokio::test]
+async fn backups_keep_the_newest_and_restore_into_an_empty_database() {
+    use object_store::{path::Path, ObjectStore};
+
+    let db = TestDb::new().await;
+    let store = Arc::new(object_store::memory::InMemory::new());
+    let backups = backup::Backups::new(store.clone(), "family", "memory".to_string(), 2);
+    // Backups are named by the real time, so older ones are written directly
+    for name in ["hot_dog-backup-20260304-020000.json", "hot_dog-backup-20260305-020000.json"] {
+        let path = Path::from("family").child(name);
+        store.put(&path, b"{}".to_vec().into()).await.unwrap();
+    }
+    let ana = kids::add_kid(&db.repo, "Ana".to_string()).await.unwrap();
+    kids::update_granularity(&db.repo, "WEEKLY".to_string())
+        .await
+        .unwrap();
+    note_at(&db, ana.id, true, "2026-03-03 08:00:00").await;
+    set_now(Some(at("2026-03-06 02:00:00")));
+    let latest = backups.back_up(&db.repo).await.unwrap();
+
+    let stored = backups.list().await.unwrap();
+    let names: Vec<_> = stored.iter().map(|b| b.name.as_str()).collect();
+    assert_eq!(names, [latest.name.as_str(), "hot_dog-backup-20260305-020000.json"]);
+
+    let bytes = backups.read(&stored[0].name).await.unwrap();
+    assert!(backup::restore(&db.repo, &stored[0].name, bytes.clone())
+        .await
+        .is_err());
+
+    let fresh = TestDb::new().await;
+    backup::restore(&fresh.repo, &stored[0].name, bytes)
+        .await
+        .unwrap();
+    assert_eq!(kids::get_granularity(&fresh.repo).await.unwrap(), "WEEKLY");
+    let restored = kids::list_kids(&fresh.repo).await.unwrap();
+    assert_eq!(count_at(&fresh, restored[0].id, "2026-03-06 09:00:00").await.0, 1);
+}
+
+#[tokio::test]
+async fn failed_restores_leave_the_settings_alone() {
+    let source = TestDb::new().await;
+    kids::add_kid(&source.repo, "Ana".to_string()).await.unwrap();
+    kids::add_kid(&source.repo, "Bia".to_string()).await.unwrap();
+    let mut export = export::build_export(&source.repo).await.unwrap();
+    export.settings.granularity = "WEEKLY".to_string();
+    // Too few for the kids in the backup
+    export.settings.max_kids = 1;
+
+    let db = TestDb::new().await;
+    let bytes = serde_json::to_vec(&export).unwrap();
+    assert!(backup::restore(&db.repo, "backup.json", bytes).await.is_err());
+    assert_eq!(kids::get_granularity(&db.repo).await.unwrap(), "MONTHLY");
+    assert!(kids::list_kids(&db.repo).await.unwrap().is_empty());
+
+    export.settings.max_kids = 2;
+    let bytes = serde_json::to_vec(&export).unwrap();
+    backup::restore(&db.repo, "backup.json", bytes).await.unwrap();
+    assert_eq!(kids::get_granularity(&db.repo).await.unwrap(), "WEEKLY");
+    assert_eq!(kids::list_kids(&db.repo).await.unwrap().len(), 2);
+    let log = audit::list_audit_log(&db.repo, None, None, None).await.unwrap();
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].action, AuditAction::BackupRestored);
+}
+
+#[tokio::test]
+async fn seeding_is_repeatable_and_spread_over_past_cycles() {
+    let db = TestDb::new().await;
+    set_now(Some(at("2026-03-10 12:00:00")));
+    let options = seed::SeedOptions {
+        kids: 2,
+        days: 60,
+        seed: 7,
+    };
+
+    let first = seed::seed_demo(&db.repo, options).await.unwrap();
+    assert_eq!(first.kids_added, 2);
+    assert!(first.notes_added > 60, "{first:?}");
+    let again = seed::seed_demo(&db.repo, options).await.unwrap();
+    assert_eq!((again.kids_added, again.notes_added), (0, 0));
+
+    let totals = stats::get_cycle_totals(&db.repo, 6).await.unwrap();
+    assert_eq!(totals.kids.len(), 2);
+    for kid in &totals.kids {
+        assert!(kid.totals.iter().filter(|t| **t != 0).count() > 1);
+    }
+}
+
+#[tokio::test]
+async fn weekly_report_compares_with_the_previous_week() {
+    let db = TestDb::new().await;
+    let ana = kids::add_kid(&db.repo, "Ana".to_string()).await.unwrap();
+    let bia = kids::add_kid(&db.repo, "Bia".to_string()).await.unwrap();
+    // Monday 2 March 2026 starts the reported week
+    note_at(&db, ana.id, true, "2026-02-27 18:00:00").await;
+    note_at(&db, bia.id, false, "2026-03-03 10:00:00").await;
+    note_at(&db, ana.id, true, "2026-03-02 08:00:00").await;
+    note_at(&db, ana.id, false, "2026-03-02 19:30:00").await;
+    note_at(&db, ana.id, true, "2026-03-04 07:15:00").await;
+    note_at(&db, ana.id, true, "2026-03-09 09:00:00").await;
+
+    set_now(Some(at("2026-03-10 12:00:00")));
+    let week = report::kid_report(&db.repo, ana.id, ReportPeriod::Week, 1, 0)
+        .await
+        .unwrap();
+    assert_eq!(week.start.to_string(), "2026-03-02");
+    assert!(!week.in_progress);
+    assert_eq!((week.total, week.positive, week.negative), (1, 2, 1));
+    assert_eq!(week.previous_total, 1);
+    assert_eq!(week.days.len(), 2);
+    assert_eq!(week.days[0].notes.len(), 2);
+
+    let html = report::render_html(&week);
+    assert!(html.contains("Week of 2 Mar 2026") && html.contains("19:30 -1"));
+    let pdf = report::report_file(&db.repo, ana.id, ReportPeriod::Month, 0, 0, ReportFormat::Pdf)
+        .await
+        .unwrap();
+    assert_eq!(pdf.file_name, "hot_dog-ana-2026-03.pdf");
+    assert!(pdf.bytes.starts_with(b"%PDF-") && pdf.bytes.ends_with(b"%%EOF\n"));
+
+    // Ten hours behind UTC, Monday's 08:00 note was still Sunday evening
+    let week = report::kid_report(&db.repo, ana.id, ReportPeriod::Week, 1, -600)
+        .await
+        .unwrap();
+    assert_eq!(week.start.to_string(), "2026-03-02");
+    assert_eq!((week.total, week.positive, week.negative), (1, 2, 1));
+    assert_eq!(week.previous_total, 2);
+    let dates: Vec<String> = week.days.iter().map(|d| d.date.to_string()).collect();
+    assert_eq!(dates, ["2026-03-02", "2026-03-03", "2026-03-08"]);
+    let html = report::render_html(&week);
+    assert!(html.contains("09:30 -1") && html.contains("Generated 10 Mar 2026 02:00"));
+    assert!(report::kid_report(&db.repo, ana.id, ReportPeriod::Week, 1, 15 * 60)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn kids_and_stats_run_without_a_database() {
+    let repo = InMemoryRepository::new();
+    kids::update_kid_limits(
+        &repo,
+        KidLimits {
+            max_kids: 2,
+            ..KidLimits::default()
+        },
+    )
+    .await
+    .unwrap();
+    let ana = kids::add_kid(&repo, "Ana".to_string()).await.unwrap();
+    kids::add_kid(&repo, "Bia".to_string()).await.unwrap();
+    assert!(kids::add_kid(&repo, "ana".to_string()).await.is_err());
+    assert!(kids::add_kid(&repo, "Caio".to_string()).await.is_err());
+    kids::update_granularity(&repo, "WEEKLY".to_string())
+        .await
+        .unwrap();
+
+    // Weeks starting Monday 2026-03-02 and 2026-03-09
+    for (add, timestamp) in [
+        (true, "2026-03-08 23:59:59"),
+        (true, "2026-03-09 00:00:00"),
+        (false, "2026-03-10 08:00:00"),
+        (true, "2026-03-10 09:00:00"),
+    ] {
+        set_now(Some(at(timestamp)));
+        kids::log_note(&repo, ana.id, add, None, None).await.unwrap();
+    }
+    set_now(Some(at("2026-03-11 12:00:00")));
+
+    let response = kids::get_kids(&repo, None).await.unwrap();
+    assert_eq!(response.kids[0].count, 1);
+    let totals = stats::get_cycle_totals(&repo, 2).await.unwrap();
+    assert_eq!(totals.kids[0].totals, [1, 1]);
+    let ratios = stats::get_note_ratios(&repo).await.unwrap();
+    assert_eq!((ratios[0].positive, ratios[0].negative), (3, 1));
+    let cells = stats::get_activity_heatmap(&repo, 0).await.unwrap();
+    assert_eq!(cells.iter().map(|c| c.count).sum::<u32>(), 4);
+    set_now(None);
+}
+
+#[tokio::test]
+async fn avatars_are_stored_through_the_repository() {
+    let repo = InMemoryRepository::new();
+    let ana = kids::add_kid(&repo, "Ana".to_string()).await.unwrap();
+    let mut png = std::io::Cursor::new(Vec::new());
+    image::RgbImage::new(8, 8)
+        .write_to(&mut png, image::ImageFormat::Png)
+        .unwrap();
+
+    assert!(avatars::upload_kid_avatar(&repo, 999, png.get_ref().clone())
+        .await
+        .is_err());
+    avatars::upload_kid_avatar(&repo, ana.id, png.into_inner())
+        .await
+        .unwrap();
+    assert!(repo.avatar(ana.id).await.unwrap().is_some());
+
+    kids::archive_kid(&repo, ana.id).await.unwrap();
+    kids::delete_kid(&repo, ana.id).await.unwrap();
+    assert!(repo.avatar(ana.id).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn avatar_photos_and_versions_change_together() {
+    let db = TestDb::new().await;
+    let ana = kids::add_kid(&db.repo, "Ana".to_string()).await.unwrap();
+    let png = |width, height| {
+        let mut png = std::io::Cursor::new(Vec::new());
+        image::GrayImage::new(width, height)
+            .write_to(&mut png, image::ImageFormat::Png)
+            .unwrap();
+        png.into_inner()
+    };
+    async fn version(repo: &LibsqlRepository, kid_id: u32) -> Option<i64> {
+        kids::find_kid(repo, kid_id).await.unwrap().unwrap().avatar_version
+    }
+
+    // Compresses to a few kilobytes, but would decode to far more than a photo needs
+    assert!(avatars::upload_kid_avatar(&db.repo, ana.id, png(20_000, 1))
+        .await
+        .is_err());
+    assert_eq!(version(&db.repo, ana.id).await, None);
+
+    let uploaded = avatars::upload_kid_avatar(&db.repo, ana.id, png(8, 8))
+        .await
+        .unwrap();
+    assert_eq!(version(&db.repo, ana.id).await, Some(uploaded));
+    assert!(db.repo.avatar(ana.id).await.unwrap().is_some());
+
+    avatars::remove_kid_avatar(&db.repo, ana.id).await.unwrap();
+    assert_eq!(version(&db.repo, ana.id).await, None);
+    assert!(db.repo.avatar(ana.id).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn counts_go_past_what_a_byte_holds() {
+    let repo = InMemoryRepository::new();
+    let ana = kids::add_kid(&repo, "Ana".to_string()).await.unwrap();
+    set_now(Some(at("2026-03-10 08:00:00")));
+    for _ in 0..200 {
+        kids::log_note(&repo, ana.id, true, None, None).await.unwrap();
+    }
+
+    let response = kids::get_kids(&repo, None).await.unwrap();
+    assert_eq!(response.kids[0].count, 200);
+    set_now(None);
+}
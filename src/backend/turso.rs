@@ -1,24 +1,72 @@
-#[cfg(feature = "server")]
-use libsql::{Builder, Connection};
-#[cfg(feature = "server")]
+use crate::backend::migrations::run_migrations;
+use libsql::{Builder, Connection, Database};
+use std::sync::Arc;
 use tokio::sync::OnceCell;
 
-#[cfg(feature = "server")]
-static CONN: OnceCell<Connection> = OnceCell::const_new();
+/// The database is kept next to its connection: embedded replicas sync in the
+/// background only while the `Database` is alive.
+static CONN: OnceCell<(Arc<Database>, Connection)> = OnceCell::const_new();
 
+/// The server talks to Turso. A `file:` URL opens a local libSQL file instead,
+/// for development and demo data; no token is needed then.
 #[cfg(feature = "server")]
-async fn init_db() -> Connection {
+async fn open_database() -> Database {
     let url = std::env::var("TURSO_DATABASE_URL").expect("TURSO_DATABASE_URL must be set");
+    if let Some(path) = url.strip_prefix("file:") {
+        return Builder::new_local(path)
+            .build()
+            .await
+            .expect("Failed to build database");
+    }
     let token = std::env::var("TURSO_AUTH_TOKEN").expect("TURSO_AUTH_TOKEN must be set");
 
-    let db = Builder::new_remote(url, token)
+    Builder::new_remote(url, token)
         .build()
         .await
-        .expect("Failed to build database");
-    db.connect().expect("Failed to connect to database")
+        .expect("Failed to build database")
+}
+
+/// Desktop and mobile builds keep their data in a file on the device
+/// (`HOT_DOG_DB_PATH`, `hot_dog.db` by default). When Turso credentials are also
+/// set, the file is an embedded replica kept in sync with the remote database.
+#[cfg(not(feature = "server"))]
+async fn open_database() -> Database {
+    let path = std::env::var("HOT_DOG_DB_PATH").unwrap_or_else(|_| "hot_dog.db".to_string());
+
+    let db = match (
+        std::env::var("TURSO_DATABASE_URL"),
+        std::env::var("TURSO_AUTH_TOKEN"),
+    ) {
+        (Ok(url), Ok(token)) => Builder::new_remote_replica(path, url, token)
+            .sync_interval(std::time::Duration::from_secs(60))
+            .read_your_writes(true)
+            .build()
+            .await
+            .expect("Failed to build database"),
+        _ => Builder::new_local(path)
+            .build()
+            .await
+            .expect("Failed to build database"),
+    };
+    // Offline start-up is fine: the replica keeps serving its last synced copy
+    let _ = db.sync().await;
+    db
+}
+
+async fn init_db() -> (Arc<Database>, Connection) {
+    let db = open_database().await;
+    let conn = db.connect().expect("Failed to connect to database");
+    run_migrations(&conn)
+        .await
+        .expect("Failed to apply database migrations");
+    (Arc::new(db), conn)
 }
 
-#[cfg(feature = "server")]
 pub async fn get_db() -> &'static Connection {
-    CONN.get_or_init(|| init_db()).await
+    &CONN.get_or_init(|| init_db()).await.1
+}
+
+/// The database behind `get_db`, for work that needs a connection of its own.
+pub async fn get_database() -> &'static Arc<Database> {
+    &CONN.get_or_init(|| init_db()).await.0
 }
@@ -0,0 +1,124 @@
+use crate::models::KidLimits;
+use dioxus::prelude::ServerFnError;
+use std::fmt;
+
+/// Bounds accepted when a family configures its own limits.
+pub const MAX_KIDS_RANGE: (u32, u32) = (1, 50);
+pub const MAX_NAME_LENGTH_RANGE: (u32, u32) = (1, 100);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    EmptyName,
+    NameTooLong(u32),
+    DuplicateName(String),
+    TooManyKids(u32),
+    LimitOutOfRange(&'static str, u32, u32),
+    InvalidUtcOffset(i32),
+    InvalidKidOrder,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::EmptyName => write!(f, "Name cannot be empty"),
+            ValidationError::NameTooLong(max) => write!(f, "Name too long (max {max} characters)"),
+            ValidationError::DuplicateName(name) => {
+                write!(f, "There is already a kid named '{name}'")
+            }
+            ValidationError::TooManyKids(max) => write!(f, "Maximum of {max} kids allowed"),
+            ValidationError::LimitOutOfRange(what, min, max) => {
+                write!(f, "{what} must be between {min} and {max}")
+            }
+            ValidationError::InvalidUtcOffset(minutes) => {
+                write!(f, "Invalid time zone offset ({minutes} minutes)")
+            }
+            ValidationError::InvalidKidOrder => {
+                write!(f, "The new order must list every kid exactly once")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl From<ValidationError> for ServerFnError {
+    fn from(e: ValidationError) -> Self {
+        ServerFnError::new(e.to_string())
+    }
+}
+
+/// Trims the name and collapses runs of inner whitespace into single spaces.
+pub fn normalize_name(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Normalizes and checks a kid name. Length is counted in characters, not bytes,
+/// so accented names get the same allowance as plain ASCII ones.
+pub fn validate_kid_name(name: &str, limits: &KidLimits) -> Result<String, ValidationError> {
+    let name = normalize_name(name);
+    if name.is_empty() {
+        return Err(ValidationError::EmptyName);
+    }
+    if name.chars().count() > limits.max_name_length as usize {
+        return Err(ValidationError::NameTooLong(limits.max_name_length));
+    }
+    Ok(name)
+}
+
+/// Returns true when both names are the same ignoring case (Unicode-aware).
+pub fn same_name(a: &str, b: &str) -> bool {
+    a.to_lowercase() == b.to_lowercase()
+}
+
+/// Fails when `name` matches any of `existing` ignoring case.
+pub fn ensure_unique_name<'a>(
+    name: &str,
+    existing: impl IntoIterator<Item = &'a str>,
+) -> Result<(), ValidationError> {
+    match existing.into_iter().find(|other| same_name(name, other)) {
+        Some(other) => Err(ValidationError::DuplicateName(other.to_string())),
+        None => Ok(()),
+    }
+}
+
+/// Fails when adding one more kid would exceed the limit.
+pub fn ensure_room_for_kid(active_kids: u32, limits: &KidLimits) -> Result<(), ValidationError> {
+    if active_kids >= limits.max_kids {
+        return Err(ValidationError::TooManyKids(limits.max_kids));
+    }
+    Ok(())
+}
+
+/// Checks limits submitted from the settings screen.
+pub fn validate_limits(limits: &KidLimits) -> Result<(), ValidationError> {
+    let (min, max) = MAX_KIDS_RANGE;
+    if !(min..=max).contains(&limits.max_kids) {
+        return Err(ValidationError::LimitOutOfRange("Maximum kids", min, max));
+    }
+    let (min, max) = MAX_NAME_LENGTH_RANGE;
+    if !(min..=max).contains(&limits.max_name_length) {
+        return Err(ValidationError::LimitOutOfRange("Maximum name length", min, max));
+    }
+    Ok(())
+}
+
+/// Checks a client's time zone, given as minutes ahead of UTC. Real offsets run
+/// from UTC-12:00 to UTC+14:00.
+pub fn validate_utc_offset(utc_offset_minutes: i32) -> Result<(), ValidationError> {
+    if !(-12 * 60..=14 * 60).contains(&utc_offset_minutes) {
+        return Err(ValidationError::InvalidUtcOffset(utc_offset_minutes));
+    }
+    Ok(())
+}
+
+/// Checks that a new display order lists each active kid exactly once, and nothing else.
+pub fn validate_kid_order(kid_ids: &[u32], active_ids: &[u32]) -> Result<(), ValidationError> {
+    let mut listed = kid_ids.to_vec();
+    let mut active = active_ids.to_vec();
+    listed.sort_unstable();
+    active.sort_unstable();
+    if listed != active {
+        return Err(ValidationError::InvalidKidOrder);
+    }
+    Ok(())
+}
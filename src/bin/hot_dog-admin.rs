@@ -0,0 +1,210 @@
+//! Command-line administration for a deployment, against the same database as the
+//! server (`TURSO_DATABASE_URL`, `TURSO_AUTH_TOKEN`). Uses the app's backend
+//! library, so every change goes through the same validation and lands in the audit
+//! log.
+//!
+//! Run `cargo run --features server --bin hot_dog-admin -- help` for the commands.
+//! Cycles have no command: they close on their own when their period ends.
+
+use hot_dog::backend::repository::repository;
+use hot_dog::backend::seed::{seed_demo, SeedOptions};
+use hot_dog::backend::{audit, backup, export, import, kids, migrations, stats, turso};
+use hot_dog::models::{ExportFormat, ImportPreview};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: hot_dog-admin <command> [arguments]
+
+Commands:
+  migrate                     Apply pending database migrations
+  kids                        List active and archived kids
+  add-kid <name>              Add a kid
+  cycles [count]              Per-kid totals for the last cycles (default 6)
+  export [--csv] <file>       Write all data as JSON, or as a zip of CSV files
+  import [--dry-run] <file>   Load a JSON or CSV zip export
+  backup                      Take a backup now (needs BACKUP_DIR or BACKUP_S3_BUCKET)
+  backups                     List stored backups
+  restore <name | file>       Restore a stored backup, or a backup file, into an empty database
+  seed [--kids N] [--days N] [--seed N]
+                              Add demo kids with notes over past days (3 kids, 400 days)
+  help                        Show this message";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    audit::set_actor("admin");
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to start the async runtime");
+
+    match runtime.block_on(run(&args)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(args: &[&str]) -> Result<(), String> {
+    match args {
+        ["migrate"] => {
+            // Opening the database applies whatever is pending
+            let conn = turso::get_db().await;
+            let version = migrations::schema_version(conn)
+                .await
+                .map_err(|e| e.to_string())?;
+            println!("Database schema at version {version}");
+        }
+        ["kids"] => {
+            let repo = repository().await;
+            let active = kids::list_kids(repo).await.map_err(|e| e.to_string())?;
+            let archived = kids::list_archived_kids(repo)
+                .await
+                .map_err(|e| e.to_string())?;
+            for kid in &active {
+                println!("{:>4}  {}", kid.id, kid.name);
+            }
+            for kid in &archived {
+                println!("{:>4}  {} (archived)", kid.id, kid.name);
+            }
+        }
+        ["add-kid", name] => {
+            let kid = kids::add_kid(repository().await, name.to_string())
+                .await
+                .map_err(|e| e.to_string())?;
+            println!("{:>4}  {}", kid.id, kid.name);
+        }
+        ["cycles"] => print_cycles(6).await?,
+        ["cycles", count] => {
+            let count = count
+                .parse()
+                .map_err(|_| format!("Not a number of cycles: '{count}'"))?;
+            print_cycles(count).await?;
+        }
+        ["export", file] => write_export(ExportFormat::Json, file).await?,
+        ["export", "--csv", file] => write_export(ExportFormat::CsvZip, file).await?,
+        ["import", file] => run_import(file, false).await?,
+        ["import", "--dry-run", file] => run_import(file, true).await?,
+        ["backup"] => {
+            let info = backup::back_up_now(repository().await)
+                .await
+                .map_err(|e| e.to_string())?;
+            println!("Wrote {} ({} bytes)", info.name, info.size);
+        }
+        ["backups"] => {
+            let status = backup::get_backup_status()
+                .await
+                .map_err(|e| e.to_string())?;
+            let Some(target) = status.target else {
+                return Err("Backups are not configured".to_string());
+            };
+            println!("{target}, keeping {}", status.keep);
+            for info in status.backups {
+                println!("  {}  {:>10} bytes", info.name, info.size);
+            }
+        }
+        ["restore", source] => {
+            let repo = repository().await;
+            // A path on disk, or the name of a stored backup
+            match std::fs::read(source) {
+                Ok(bytes) => backup::restore(repo, source, bytes).await,
+                Err(_) => backup::restore_backup(repo, source.to_string()).await,
+            }
+            .map_err(|e| e.to_string())?;
+            println!("Restored {source}");
+        }
+        ["seed", options @ ..] => {
+            let options = seed_options(options)?;
+            let counts = seed_demo(repository().await, options)
+                .await
+                .map_err(|e| e.to_string())?;
+            println!(
+                "Added {} kids and {} notes",
+                counts.kids_added, counts.notes_added
+            );
+        }
+        ["help"] | ["--help"] | ["-h"] => println!("{USAGE}"),
+        _ => return Err(format!("unknown command\n\n{USAGE}")),
+    }
+    Ok(())
+}
+
+fn seed_options(args: &[&str]) -> Result<SeedOptions, String> {
+    let mut options = SeedOptions::default();
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value for {flag}"))?;
+        let not_a_number = |_| format!("Not a number: '{value}'");
+        match *flag {
+            "--kids" => options.kids = value.parse().map_err(not_a_number)?,
+            "--days" => options.days = value.parse().map_err(not_a_number)?,
+            "--seed" => options.seed = value.parse().map_err(not_a_number)?,
+            _ => return Err(format!("Unknown option: {flag}")),
+        }
+    }
+    Ok(options)
+}
+
+async fn print_cycles(count: u32) -> Result<(), String> {
+    let totals = stats::get_cycle_totals(repository().await, count)
+        .await
+        .map_err(|e| e.to_string())?;
+    // Cycles close on their own once the clock passes their end; this shows where they stand
+    println!("{} cycles", totals.granularity.to_lowercase());
+    print!("{:<20}", "");
+    for period in &totals.periods {
+        print!("{period:>10}");
+    }
+    println!();
+    for kid in &totals.kids {
+        print!("{:<20}", kid.name);
+        for total in &kid.totals {
+            print!("{total:>10}");
+        }
+        println!();
+    }
+    Ok(())
+}
+
+async fn write_export(format: ExportFormat, file: &str) -> Result<(), String> {
+    let export = export::export_data(repository().await, format)
+        .await
+        .map_err(|e| e.to_string())?;
+    std::fs::write(file, &export.bytes).map_err(|e| format!("{file}: {e}"))?;
+    println!("Wrote {file} ({} bytes)", export.bytes.len());
+    Ok(())
+}
+
+async fn run_import(file: &str, dry_run: bool) -> Result<(), String> {
+    let bytes = std::fs::read(file).map_err(|e| format!("{file}: {e}"))?;
+    let preview = import::import_data(repository().await, bytes, dry_run)
+        .await
+        .map_err(|e| e.to_string())?;
+    print_preview(&preview);
+    if !preview.errors.is_empty() {
+        return Err("nothing was imported".to_string());
+    }
+    Ok(())
+}
+
+fn print_preview(preview: &ImportPreview) {
+    for error in &preview.errors {
+        println!("  {error}");
+    }
+    if !preview.errors.is_empty() {
+        return;
+    }
+    let verb = if preview.committed { "Imported" } else { "Would import" };
+    println!(
+        "{verb}: {} new kids, {} matched, {} notes ({} already there)",
+        preview.kids_added.len(),
+        preview.kids_matched.len(),
+        preview.notes_added,
+        preview.notes_skipped
+    );
+}
@@ -1,42 +1,96 @@
 use crate::components::button::*;
-use crate::models::Kid;
+use crate::models::{age_on, avatar_url, Kid};
+use chrono::{Datelike, NaiveDate};
 use dioxus::prelude::*;
 
+/// Palette offered when picking a kid's color, also used for name-derived defaults.
+pub const KID_COLORS: &[&str] = &[
+    "#6366f1", // indigo
+    "#8b5cf6", // violet
+    "#ec4899", // pink
+    "#f43f5e", // rose
+    "#f97316", // orange
+    "#eab308", // yellow
+    "#22c55e", // green
+    "#14b8a6", // teal
+    "#06b6d4", // cyan
+    "#3b82f6", // blue
+];
+
 /// Returns a color based on the kid's name for the avatar circle.
-fn kid_color(name: &str) -> &'static str {
+pub fn kid_color(name: &str) -> &'static str {
     let hash: u32 = name
         .bytes()
         .fold(0u32, |acc, b| acc.wrapping_mul(31).wrapping_add(b as u32));
-    let colors = [
-        "#6366f1", // indigo
-        "#8b5cf6", // violet
-        "#ec4899", // pink
-        "#f43f5e", // rose
-        "#f97316", // orange
-        "#eab308", // yellow
-        "#22c55e", // green
-        "#14b8a6", // teal
-        "#06b6d4", // cyan
-        "#3b82f6", // blue
-    ];
-    colors[(hash as usize) % colors.len()]
+    KID_COLORS[(hash as usize) % KID_COLORS.len()]
+}
+
+/// Returns the kid's chosen color, falling back to the name-derived one.
+pub fn avatar_color(color: Option<&str>, name: &str) -> String {
+    color.unwrap_or_else(|| kid_color(name)).to_string()
+}
+
+/// Returns the kid's emoji avatar, or the uppercased initial of their name.
+pub fn avatar_text(emoji: Option<&str>, name: &str) -> String {
+    match emoji {
+        Some(emoji) => emoji.to_string(),
+        None => name.chars().next().unwrap_or('?').to_uppercase().to_string(),
+    }
+}
+
+/// Round avatar showing the kid's photo, or their emoji/initial on their color.
+#[component]
+pub fn KidAvatar(
+    name: String,
+    color: Option<String>,
+    emoji: Option<String>,
+    photo_url: Option<String>,
+    /// CSS size of the circle, e.g. "2.5rem"
+    size: &'static str,
+    font_size: &'static str,
+) -> Element {
+    let color = avatar_color(color.as_deref(), &name);
+    let text = avatar_text(emoji.as_deref(), &name);
+
+    rsx! {
+        if let Some(photo_url) = photo_url {
+            img {
+                src: "{photo_url}",
+                alt: "{name}",
+                style: "flex-shrink: 0; width: {size}; height: {size}; border-radius: 50%; object-fit: cover; background-color: {color};",
+            }
+        } else {
+            div { style: "flex-shrink: 0; width: {size}; height: {size}; border-radius: 50%; display: flex; align-items: center; justify-content: center; color: white; font-size: {font_size}; font-weight: 700; background-color: {color};",
+                "{text}"
+            }
+        }
+    }
 }
 
 #[component]
 pub fn KidCard(
     kid: Kid,
+    /// Position in the leaderboard, when leaderboard mode is enabled.
+    rank: Option<u32>,
+    /// Today on the backend's clock, which may be time travelling.
+    today: NaiveDate,
+    /// Hides the +/- buttons, for cycles that are already closed.
+    #[props(default)]
+    read_only: bool,
     on_increment: EventHandler<u32>,
     on_decrement: EventHandler<u32>,
 ) -> Element {
     let kid_id = kid.id;
-    let initial = kid
-        .name
-        .chars()
-        .next()
-        .unwrap_or('?')
-        .to_uppercase()
-        .to_string();
-    let color = kid_color(&kid.name);
+    let age = kid.birthdate.and_then(|b| age_on(b, today));
+    let is_birthday = kid
+        .birthdate
+        .is_some_and(|b| b.month() == today.month() && b.day() == today.day());
+    let rank_label = rank.map(|r| match r {
+        1 => "🥇".to_string(),
+        2 => "🥈".to_string(),
+        3 => "🥉".to_string(),
+        n => format!("#{n}"),
+    });
     let latest_note = kid
         .latest_note
         .map(|dt| dt.format("%b %d, %Y %H:%M").to_string())
@@ -49,25 +103,41 @@ pub fn KidCard(
             div { style: "display: flex; align-items: center; gap: 0.875rem; padding: 1rem 1.25rem;",
 
                 // Avatar
-                div { style: "flex-shrink: 0; width: 2.5rem; height: 2.5rem; border-radius: 50%; display: flex; align-items: center; justify-content: center; color: white; font-size: 0.875rem; font-weight: 700; background-color: {color};",
-                    "{initial}"
+                KidAvatar {
+                    name: kid.name.clone(),
+                    color: kid.color.clone(),
+                    emoji: kid.emoji.clone(),
+                    photo_url: avatar_url(kid.id, kid.avatar_version),
+                    size: "2.5rem",
+                    font_size: "0.875rem",
                 }
 
                 // Name + count
                 div { style: "flex: 1; min-width: 0;",
                     p { style: "font-size: 1rem; font-weight: 600; color: #111827; line-height: 1.3;",
                         "{kid.name}"
+                        if is_birthday {
+                            span { style: "margin-left: 0.375rem;", title: "Happy birthday!", "🎂" }
+                        }
+                        if let Some(rank_label) = rank_label {
+                            span { style: "margin-left: 0.375rem; font-size: 0.75rem; font-weight: 600; color: #9ca3af;",
+                                "{rank_label}"
+                            }
+                        }
                     }
                     p { style: "font-size: 0.8125rem; color: #9ca3af; margin-top: 2px;",
                         "Count: "
                         span { style: "font-weight: 600; color: #374151; font-size: 0.875rem;",
                             "{kid.count}"
                         }
+                        if let Some(age) = age {
+                            " · {age} y/o"
+                        }
                     }
                 }
 
                 // Action buttons
-                div { style: "display: flex; align-items: center; gap: 0.5rem; flex-shrink: 0;",
+                div { style: if read_only { "display: none;" } else { "display: flex; align-items: center; gap: 0.5rem; flex-shrink: 0;" },
                     // Decrement (red minus)
                     Button {
                         style: "background-color: #fee2e2; color: #dc2626; padding: 8px; border-radius: 0.5rem;",
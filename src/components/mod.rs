@@ -5,4 +5,5 @@ pub mod card;
 pub mod kid_card;
 pub mod popover;
 pub mod settings;
+pub mod stats;
 pub mod toast;
@@ -1,7 +1,16 @@
-use crate::backend::kids::{
-    add_kid, delete_kid, get_granularity, list_kids, rename_kid, update_granularity,
+use crate::backend::api::{Api, Backend};
+use crate::backend::avatars::MAX_AVATAR_BYTES;
+use crate::backend::events::subscribe_changes;
+use crate::backend::validation::{
+    ensure_unique_name, validate_kid_name, validate_limits, ValidationError, MAX_KIDS_RANGE,
+    MAX_NAME_LENGTH_RANGE,
 };
 use crate::components::button::*;
+use crate::components::kid_card::{KidAvatar, KID_COLORS};
+use crate::models::{
+    avatar_url, AuditAction, ChangeEvent, ExportFile, ExportFormat, ImportPreview, KidLimits,
+    MAX_BACKDATE_DAYS, MAX_IMPORT_BYTES,
+};
 use crate::components::popover::*;
 use crate::Route;
 use dioxus::prelude::*;
@@ -15,42 +24,135 @@ const GRANULARITY_OPTIONS: &[(&str, &str)] = &[
     ("YEARLY", "Yearly"),
 ];
 
-/// Returns a color based on the kid's name for the avatar circle.
-fn kid_color(name: &str) -> &'static str {
-    let hash: u32 = name
-        .bytes()
-        .fold(0u32, |acc, b| acc.wrapping_mul(31).wrapping_add(b as u32));
-    let colors = [
-        "#6366f1", // indigo
-        "#8b5cf6", // violet
-        "#ec4899", // pink
-        "#f43f5e", // rose
-        "#f97316", // orange
-        "#eab308", // yellow
-        "#22c55e", // green
-        "#14b8a6", // teal
-        "#06b6d4", // cyan
-        "#3b82f6", // blue
-    ];
-    colors[(hash as usize) % colors.len()]
+/// Returns `ids` with `moved` taking the place of `target`, shifting the kids in between.
+fn reorder(ids: &[u32], moved: u32, target: u32) -> Vec<u32> {
+    let mut ids = ids.to_vec();
+    let from = ids.iter().position(|id| *id == moved);
+    let to = ids.iter().position(|id| *id == target);
+    if let (Some(from), Some(to)) = (from, to) {
+        let id = ids.remove(from);
+        ids.insert(to, id);
+    }
+    ids
 }
 
 #[component]
 pub fn SettingsPage() -> Element {
-    let mut granularity = use_resource(get_granularity);
-    let mut popover_open = use_signal(|| false);
+    // Loaded while rendering on the server and hydrated on the client
+    let mut granularity = use_server_future(Api::get_granularity)?;
+    let mut leaderboard = use_server_future(Api::get_leaderboard)?;
+    let mut kids_resource = use_server_future(Api::list_kids)?;
+    let mut archived_resource = use_server_future(Api::list_archived_kids)?;
+    let mut kid_limits = use_server_future(Api::get_kid_limits)?;
+    let mut backdate_closed = use_server_future(Api::get_backdate_closed_cycles)?;
 
-    let mut kids_resource = use_resource(list_kids);
+    let mut popover_open = use_signal(|| false);
+    let mut show_archived = use_signal(|| false);
+    let mut confirm_delete_id: Signal<Option<u32>> = use_signal(|| None);
     let mut new_kid_name = use_signal(|| String::new());
     let mut editing_kid_id: Signal<Option<u32>> = use_signal(|| None);
 
     let mut edit_name = use_signal(|| String::new());
+    let mut dragged_kid_id: Signal<Option<u32>> = use_signal(|| None);
+
+    let mut profile_kid_id: Signal<Option<u32>> = use_signal(|| None);
+    let mut profile_color: Signal<Option<String>> = use_signal(|| None);
+    let mut profile_emoji = use_signal(|| String::new());
+    let mut profile_birthdate = use_signal(|| String::new());
+
+    // Reload whatever another device changed
+    use_future(move || async move {
+        let Ok(mut changes) = subscribe_changes().await else {
+            return;
+        };
+        while let Some(Ok(event)) = changes.recv().await {
+            match event {
+                ChangeEvent::KidCount(_) => {}
+                ChangeEvent::KidsChanged => {
+                    kids_resource.restart();
+                    archived_resource.restart();
+                }
+                ChangeEvent::SettingsChanged => {
+                    granularity.restart();
+                    leaderboard.restart();
+                    kid_limits.restart();
+                    backdate_closed.restart();
+                }
+            }
+        }
+    });
 
     let current = match &*granularity.read() {
         Some(Ok(g)) => g.clone(),
         _ => "MONTHLY".to_string(),
     };
 
+    let leaderboard_enabled = matches!(&*leaderboard.read(), Some(Ok(true)));
+    let backdate_closed_enabled = matches!(&*backdate_closed.read(), Some(Ok(true)));
+
+    let limits = match &*kid_limits.read() {
+        Some(Ok(l)) => *l,
+        _ => KidLimits::default(),
+    };
+
+    // Checks the name with the same rules as the server before submitting it
+    let mut submit_new_kid = move || {
+        let existing: Vec<String> = match &*kids_resource.read() {
+            Some(Ok(kids)) => kids.iter().map(|k| k.name.clone()).collect(),
+            _ => Vec::new(),
+        };
+        let checked = validate_kid_name(&new_kid_name(), &limits).and_then(|name| {
+            ensure_unique_name(&name, existing.iter().map(String::as_str)).map(|_| name)
+        });
+        match checked {
+            Ok(name) => {
+                new_kid_name.set(String::new());
+                spawn(async move {
+                    if let Err(e) = Api::add_kid(name).await {
+                        let toast = consume_toast();
+                        toast.error(
+                            "Failed to add kid".to_string(),
+                            ToastOptions::new()
+                                .description(format!("{e}"))
+                                .duration(Duration::from_secs(5)),
+                        );
+                    }
+                    kids_resource.restart();
+                });
+            }
+            Err(ValidationError::EmptyName) => {}
+            Err(e) => {
+                let toast = consume_toast();
+                toast.error(
+                    "Failed to add kid".to_string(),
+                    ToastOptions::new()
+                        .description(format!("{e}"))
+                        .duration(Duration::from_secs(5)),
+                );
+            }
+        }
+    };
+
+    // Saves new limits after checking them locally
+    let save_limits = move |new_limits: KidLimits| {
+        spawn(async move {
+            let result = match validate_limits(&new_limits) {
+                Ok(()) => Api::update_kid_limits(new_limits).await,
+                Err(e) => Err(e.into()),
+            };
+            if let Err(e) = result {
+                let toast = consume_toast();
+                toast.error(
+                    "Failed to update limits".to_string(),
+                    ToastOptions::new()
+                        .description(format!("{e}"))
+                        .duration(Duration::from_secs(5)),
+                );
+            }
+            kid_limits.restart();
+        });
+    };
+
     let current_label = GRANULARITY_OPTIONS
         .iter()
         .find(|(val, _)| *val == current.as_str())
@@ -68,6 +170,9 @@ pub fn SettingsPage() -> Element {
             .action-btn:hover {{ background-color: #fef3c7; }}
             .action-btn.delete:hover {{ background-color: #fee2e2; color: #ef4444; }}
             .action-btn.reset:hover {{ background-color: #ffedd5; color: #f97316; }}
+            .action-btn.profile:hover {{ background-color: #e0e7ff; color: #4f46e5; }}
+            .kid-row .drag-handle {{ cursor: grab; color: #d1d5db; }}
+            .kid-row.dragging {{ opacity: 0.4; }}
         " }
 
         div { style: "max-width: 520px; margin: 0 auto;",
@@ -119,18 +224,55 @@ pub fn SettingsPage() -> Element {
                                             let kid_id = kid.id;
                                             let kid_name = kid.name.clone();
                                             let is_editing = editing_kid_id() == Some(kid_id);
-                                            let initial = kid.name.chars().next().unwrap_or('?').to_uppercase().to_string();
-                                            let color = kid_color(&kid.name);
+                                            let kid_ids: Vec<u32> = kids.iter().map(|k| k.id).collect();
+                                            let is_dragging = dragged_kid_id() == Some(kid_id);
+                                            let is_profile_open = profile_kid_id() == Some(kid_id);
+                                            let kid_profile = kid.clone();
+                                            let has_photo = kid.avatar_version.is_some();
 
                                             rsx! {
                                                 div {
-                                                    class: "kid-row",
+                                                    class: if is_dragging { "kid-row dragging" } else { "kid-row" },
                                                     style: "display: flex; align-items: center; gap: 0.75rem; padding: 0.625rem 1.25rem; border-top: 1px solid #f3f4f6; transition: background-color 0.15s;",
+                                                    draggable: "true",
+                                                    ondragstart: move |_| dragged_kid_id.set(Some(kid_id)),
+                                                    ondragend: move |_| dragged_kid_id.set(None),
+                                                    ondragover: move |e: Event<DragData>| e.prevent_default(),
+                                                    ondrop: move |e: Event<DragData>| {
+                                                        e.prevent_default();
+                                                        let Some(moved) = dragged_kid_id() else {
+                                                            return;
+                                                        };
+                                                        dragged_kid_id.set(None);
+                                                        if moved == kid_id {
+                                                            return;
+                                                        }
+                                                        let new_order = reorder(&kid_ids, moved, kid_id);
+                                                        spawn(async move {
+                                                            if let Err(e) = Api::reorder_kids(new_order).await {
+                                                                let toast = consume_toast();
+                                                                toast.error(
+                                                                    "Failed to reorder kids".to_string(),
+                                                                    ToastOptions::new()
+                                                                        .description(format!("{e}"))
+                                                                        .duration(Duration::from_secs(5)),
+                                                                );
+                                                            }
+                                                            kids_resource.restart();
+                                                        });
+                                                    },
+
+                                                    // Drag handle
+                                                    span { class: "drag-handle", title: "Drag to reorder", "⠿" }
 
                                                     // Avatar circle
-                                                    div {
-                                                        style: "flex-shrink: 0; width: 2rem; height: 2rem; border-radius: 50%; display: flex; align-items: center; justify-content: center; color: white; font-size: 0.75rem; font-weight: 700; background-color: {color};",
-                                                        "{initial}"
+                                                    KidAvatar {
+                                                        name: kid.name.clone(),
+                                                        color: kid.color.clone(),
+                                                        emoji: kid.emoji.clone(),
+                                                        photo_url: avatar_url(kid.id, kid.avatar_version),
+                                                        size: "2rem",
+                                                        font_size: "0.75rem",
                                                     }
 
                                                     if is_editing {
@@ -147,7 +289,7 @@ pub fn SettingsPage() -> Element {
                                                                     if !new_name.is_empty() {
                                                                         editing_kid_id.set(None);
                                                                         spawn(async move {
-                                                                            if let Err(e) = rename_kid(kid_id, new_name).await {
+                                                                            if let Err(e) = Api::rename_kid(kid_id, new_name).await {
                                                                                 let toast = consume_toast();
                                                                                 toast.error(
                                                                                     "Failed to rename kid".to_string(),
@@ -189,6 +331,41 @@ pub fn SettingsPage() -> Element {
                                                             class: "kid-actions",
                                                             style: "display: flex; align-items: center; gap: 0.25rem;",
 
+                                                            // Profile button
+                                                            button {
+                                                                class: "action-btn profile",
+                                                                style: "display: flex; align-items: center; justify-content: center; padding: 0.375rem; border-radius: 0.375rem; border: none; cursor: pointer; color: #9ca3af; background: transparent;",
+                                                                title: "Edit profile",
+                                                                onclick: move |_| {
+                                                                    if is_profile_open {
+                                                                        profile_kid_id.set(None);
+                                                                    } else {
+                                                                        profile_kid_id.set(Some(kid_id));
+                                                                        profile_color.set(kid_profile.color.clone());
+                                                                        profile_emoji.set(kid_profile.emoji.clone().unwrap_or_default());
+                                                                        profile_birthdate.set(
+                                                                            kid_profile
+                                                                                .birthdate
+                                                                                .map(|d| d.format("%Y-%m-%d").to_string())
+                                                                                .unwrap_or_default(),
+                                                                        );
+                                                                    }
+                                                                },
+                                                                svg {
+                                                                    xmlns: "http://www.w3.org/2000/svg",
+                                                                    fill: "none",
+                                                                    view_box: "0 0 24 24",
+                                                                    stroke_width: "2",
+                                                                    stroke: "currentColor",
+                                                                    class: "h-4 w-4",
+                                                                    path {
+                                                                        stroke_linecap: "round",
+                                                                        stroke_linejoin: "round",
+                                                                        d: "M15.75 6a3.75 3.75 0 1 1-7.5 0 3.75 3.75 0 0 1 7.5 0ZM4.501 20.118a7.5 7.5 0 0 1 14.998 0A17.933 17.933 0 0 1 12 21.75c-2.676 0-5.216-.584-7.499-1.632Z",
+                                                                    }
+                                                                }
+                                                            }
+
                                                             // Reset counter button
                                                             button {
                                                                 class: "action-btn reset",
@@ -219,23 +396,24 @@ pub fn SettingsPage() -> Element {
                                                                 }
                                                             }
 
-                                                            // Delete button
+                                                            // Archive button
                                                             button {
                                                                 class: "action-btn delete",
                                                                 style: "display: flex; align-items: center; justify-content: center; padding: 0.375rem; border-radius: 0.375rem; border: none; cursor: pointer; color: #9ca3af; background: transparent;",
-                                                                title: "Remove kid",
+                                                                title: "Archive kid",
                                                                 onclick: move |_| {
                                                                     spawn(async move {
-                                                                        if let Err(e) = delete_kid(kid_id).await {
+                                                                        if let Err(e) = Api::archive_kid(kid_id).await {
                                                                             let toast = consume_toast();
                                                                             toast.error(
-                                                                                "Failed to delete kid".to_string(),
+                                                                                "Failed to archive kid".to_string(),
                                                                                 ToastOptions::new()
                                                                                     .description(format!("{e}"))
                                                                                     .duration(Duration::from_secs(5)),
                                                                             );
                                                                         }
                                                                         kids_resource.restart();
+                                                                        archived_resource.restart();
                                                                     });
                                                                 },
                                                                 svg {
@@ -248,13 +426,129 @@ pub fn SettingsPage() -> Element {
                                                                     path {
                                                                         stroke_linecap: "round",
                                                                         stroke_linejoin: "round",
-                                                                        d: "m14.74 9-.346 9m-4.788 0L9.26 9m9.968-3.21c.342.052.682.107 1.022.166m-1.022-.165L18.16 19.673a2.25 2.25 0 0 1-2.244 2.077H8.084a2.25 2.25 0 0 1-2.244-2.077L4.772 5.79m14.456 0a48.108 48.108 0 0 0-3.478-.397m-12 .562c.34-.059.68-.114 1.022-.165m0 0a48.11 48.11 0 0 1 3.478-.397m7.5 0v-.916c0-1.18-.91-2.164-2.09-2.201a51.964 51.964 0 0 0-3.32 0c-1.18.037-2.09 1.022-2.09 2.201v.916m7.5 0a48.667 48.667 0 0 0-7.5 0",
+                                                                        d: "m20.25 7.5-.625 10.632a2.25 2.25 0 0 1-2.247 2.118H6.622a2.25 2.25 0 0 1-2.247-2.118L3.75 7.5M10 11.25h4M3.375 7.5h17.25c.621 0 1.125-.504 1.125-1.125v-1.5c0-.621-.504-1.125-1.125-1.125H3.375c-.621 0-1.125.504-1.125 1.125v1.5c0 .621.504 1.125 1.125 1.125Z",
                                                                     }
                                                                 }
                                                             }
                                                         }
                                                     }
                                                 }
+                                                if is_profile_open {
+                                                    div { style: "padding: 0.75rem 1.25rem 1rem 3.75rem; background-color: #f9fafb; border-top: 1px solid #f3f4f6; display: flex; flex-direction: column; gap: 0.75rem;",
+                                                        // Color swatches
+                                                        div { style: "display: flex; flex-wrap: wrap; gap: 0.375rem;",
+                                                            for swatch in KID_COLORS.iter().copied() {
+                                                                button {
+                                                                    style: if profile_color().as_deref() == Some(swatch) { "width: 1.5rem; height: 1.5rem; border-radius: 50%; border: 2px solid #111; cursor: pointer; background-color: {swatch};" } else { "width: 1.5rem; height: 1.5rem; border-radius: 50%; border: 2px solid transparent; cursor: pointer; background-color: {swatch};" },
+                                                                    title: "{swatch}",
+                                                                    onclick: move |_| profile_color.set(Some(swatch.to_string())),
+                                                                }
+                                                            }
+                                                        }
+                                                        div { style: "display: flex; gap: 0.75rem;",
+                                                            label { style: "flex: 1; font-size: 0.75rem; color: #6b7280;",
+                                                                "Avatar"
+                                                                input {
+                                                                    style: "display: block; width: 100%; margin-top: 0.25rem; border-radius: 0.5rem; border: 1px solid #e5e7eb; background: white; padding: 0.375rem 0.75rem; font-size: 0.875rem; outline: none;",
+                                                                    r#type: "text",
+                                                                    placeholder: "e.g. 🦖",
+                                                                    value: "{profile_emoji}",
+                                                                    oninput: move |e: Event<FormData>| profile_emoji.set(e.value()),
+                                                                }
+                                                            }
+                                                            label { style: "flex: 1; font-size: 0.75rem; color: #6b7280;",
+                                                                "Birthdate"
+                                                                input {
+                                                                    style: "display: block; width: 100%; margin-top: 0.25rem; border-radius: 0.5rem; border: 1px solid #e5e7eb; background: white; padding: 0.375rem 0.75rem; font-size: 0.875rem; outline: none;",
+                                                                    r#type: "date",
+                                                                    value: "{profile_birthdate}",
+                                                                    oninput: move |e: Event<FormData>| profile_birthdate.set(e.value()),
+                                                                }
+                                                            }
+                                                        }
+                                                        // Photo upload
+                                                        div { style: "display: flex; align-items: center; gap: 0.5rem; font-size: 0.75rem; color: #6b7280;",
+                                                            "Photo"
+                                                            input {
+                                                                style: "flex: 1; font-size: 0.75rem;",
+                                                                r#type: "file",
+                                                                accept: "image/*",
+                                                                onchange: move |e: Event<FormData>| async move {
+                                                                    let Some(file) = e.files().into_iter().next() else {
+                                                                        return;
+                                                                    };
+                                                                    let result = if file.size() as usize > MAX_AVATAR_BYTES {
+                                                                        Err(ServerFnError::new("Image too large (max 5 MB)".to_string()))
+                                                                    } else {
+                                                                        match file.read_bytes().await {
+                                                                            Ok(bytes) => Api::upload_kid_avatar(kid_id, bytes.to_vec()).await.map(|_| ()),
+                                                                            Err(e) => Err(ServerFnError::new(e.to_string())),
+                                                                        }
+                                                                    };
+                                                                    if let Err(e) = result {
+                                                                        let toast = consume_toast();
+                                                                        toast.error(
+                                                                            "Failed to upload photo".to_string(),
+                                                                            ToastOptions::new()
+                                                                                .description(format!("{e}"))
+                                                                                .duration(Duration::from_secs(5)),
+                                                                        );
+                                                                    }
+                                                                    kids_resource.restart();
+                                                                },
+                                                            }
+                                                            if has_photo {
+                                                                button {
+                                                                    style: "font-size: 0.75rem; color: #ef4444; padding: 0.25rem 0.5rem; border-radius: 0.25rem; border: none; cursor: pointer; background: transparent;",
+                                                                    onclick: move |_| {
+                                                                        spawn(async move {
+                                                                            if let Err(e) = Api::remove_kid_avatar(kid_id).await {
+                                                                                let toast = consume_toast();
+                                                                                toast.error(
+                                                                                    "Failed to remove photo".to_string(),
+                                                                                    ToastOptions::new()
+                                                                                        .description(format!("{e}"))
+                                                                                        .duration(Duration::from_secs(5)),
+                                                                                );
+                                                                            }
+                                                                            kids_resource.restart();
+                                                                        });
+                                                                    },
+                                                                    "Remove photo"
+                                                                }
+                                                            }
+                                                        }
+                                                        div { style: "display: flex; justify-content: flex-end; gap: 0.5rem;",
+                                                            Button {
+                                                                variant: ButtonVariant::Ghost,
+                                                                onclick: move |_| profile_kid_id.set(None),
+                                                                "Cancel"
+                                                            }
+                                                            Button {
+                                                                variant: ButtonVariant::Primary,
+                                                                onclick: move |_| {
+                                                                    let color = profile_color();
+                                                                    let emoji = Some(profile_emoji()).filter(|e| !e.trim().is_empty());
+                                                                    let birthdate = chrono::NaiveDate::parse_from_str(&profile_birthdate(), "%Y-%m-%d").ok();
+                                                                    profile_kid_id.set(None);
+                                                                    spawn(async move {
+                                                                        if let Err(e) = Api::update_kid_profile(kid_id, color, emoji, birthdate).await {
+                                                                            let toast = consume_toast();
+                                                                            toast.error(
+                                                                                "Failed to update profile".to_string(),
+                                                                                ToastOptions::new()
+                                                                                    .description(format!("{e}"))
+                                                                                    .duration(Duration::from_secs(5)),
+                                                                            );
+                                                                        }
+                                                                        kids_resource.restart();
+                                                                    });
+                                                                },
+                                                                "Save"
+                                                            }
+                                                        }
+                                                    }
+                                                }
                                             }
                                         }
                                     }
@@ -262,7 +556,7 @@ pub fn SettingsPage() -> Element {
                             }
 
                             // Add new kid input
-                            if kids.len() < 10 {
+                            if kids.len() < limits.max_kids as usize {
                                 div { style: "padding: 0.75rem 1.25rem; background-color: #f9fafb; border-top: 1px solid #f3f4f6;",
                                     div { class: "flex gap-2",
                                         input {
@@ -273,52 +567,20 @@ pub fn SettingsPage() -> Element {
                                             oninput: move |e: Event<FormData>| new_kid_name.set(e.value()),
                                             onkeydown: move |e: Event<KeyboardData>| {
                                                 if e.key() == Key::Enter {
-                                                    let name = new_kid_name().trim().to_string();
-                                                    if !name.is_empty() {
-                                                        new_kid_name.set(String::new());
-                                                        spawn(async move {
-                                                            if let Err(e) = add_kid(name).await {
-                                                                let toast = consume_toast();
-                                                                toast.error(
-                                                                    "Failed to add kid".to_string(),
-                                                                    ToastOptions::new()
-                                                                        .description(format!("{e}"))
-                                                                        .duration(Duration::from_secs(5)),
-                                                                );
-                                                            }
-                                                            kids_resource.restart();
-                                                        });
-                                                    }
+                                                    submit_new_kid();
                                                 }
                                             },
                                         }
                                         Button {
                                             variant: ButtonVariant::Primary,
-                                            onclick: move |_| {
-                                                let name = new_kid_name().trim().to_string();
-                                                if !name.is_empty() {
-                                                    new_kid_name.set(String::new());
-                                                    spawn(async move {
-                                                        if let Err(e) = add_kid(name).await {
-                                                            let toast = consume_toast();
-                                                            toast.error(
-                                                                "Failed to add kid".to_string(),
-                                                                ToastOptions::new()
-                                                                    .description(format!("{e}"))
-                                                                    .duration(Duration::from_secs(5)),
-                                                            );
-                                                        }
-                                                        kids_resource.restart();
-                                                    });
-                                                }
-                                            },
+                                            onclick: move |_| submit_new_kid(),
                                             "Add"
                                         }
                                     }
                                 }
                             } else {
                                 div { style: "padding: 0.75rem 1.25rem; background-color: #fffbeb; border-top: 1px solid #fef3c7;",
-                                    p { class: "text-xs", style: "color: #d97706;", "Maximum of 10 kids reached." }
+                                    p { class: "text-xs", style: "color: #d97706;", "Maximum of {limits.max_kids} kids reached." }
                                 }
                             }
                         },
@@ -333,6 +595,92 @@ pub fn SettingsPage() -> Element {
                             }
                         },
                     }
+
+                    // Archived kids (restore or delete permanently)
+                    if let Some(Ok(archived)) = &*archived_resource.read() {
+                        if !archived.is_empty() {
+                            div { style: "border-top: 1px solid #f3f4f6;",
+                                button {
+                                    style: "width: 100%; padding: 0.625rem 1.25rem; text-align: left; font-size: 0.75rem; font-weight: 500; color: #9ca3af; border: none; cursor: pointer; background: transparent;",
+                                    onclick: move |_| show_archived.set(!show_archived()),
+                                    if show_archived() { "▾ Archived ({archived.len()})" } else { "▸ Archived ({archived.len()})" }
+                                }
+                                if show_archived() {
+                                    for kid in archived.iter() {
+                                        {
+                                            let kid_id = kid.id;
+                                            let confirming = confirm_delete_id() == Some(kid_id);
+                                            rsx! {
+                                                div { style: "display: flex; align-items: center; gap: 0.75rem; padding: 0.5rem 1.25rem; border-top: 1px solid #f3f4f6; opacity: 0.75;",
+                                                    KidAvatar {
+                                                        name: kid.name.clone(),
+                                                        color: kid.color.clone(),
+                                                        emoji: kid.emoji.clone(),
+                                                        photo_url: avatar_url(kid.id, kid.avatar_version),
+                                                        size: "1.75rem",
+                                                        font_size: "0.6875rem",
+                                                    }
+                                                    span { style: "flex: 1; font-size: 0.875rem; color: #6b7280;", "{kid.name}" }
+                                                    if confirming {
+                                                        span { style: "font-size: 0.75rem; color: #ef4444;", "Delete with all notes?" }
+                                                        button {
+                                                            style: "font-size: 0.75rem; color: #9ca3af; padding: 0.25rem 0.5rem; border-radius: 0.25rem; border: none; cursor: pointer; background: transparent;",
+                                                            onclick: move |_| confirm_delete_id.set(None),
+                                                            "Cancel"
+                                                        }
+                                                        Button {
+                                                            variant: ButtonVariant::Destructive,
+                                                            onclick: move |_| {
+                                                                confirm_delete_id.set(None);
+                                                                spawn(async move {
+                                                                    if let Err(e) = Api::delete_kid(kid_id).await {
+                                                                        let toast = consume_toast();
+                                                                        toast.error(
+                                                                            "Failed to delete kid".to_string(),
+                                                                            ToastOptions::new()
+                                                                                .description(format!("{e}"))
+                                                                                .duration(Duration::from_secs(5)),
+                                                                        );
+                                                                    }
+                                                                    archived_resource.restart();
+                                                                });
+                                                            },
+                                                            "Delete forever"
+                                                        }
+                                                    } else {
+                                                        button {
+                                                            style: "font-size: 0.75rem; color: #2563eb; padding: 0.25rem 0.5rem; border-radius: 0.25rem; border: none; cursor: pointer; background: transparent;",
+                                                            onclick: move |_| {
+                                                                spawn(async move {
+                                                                    if let Err(e) = Api::restore_kid(kid_id).await {
+                                                                        let toast = consume_toast();
+                                                                        toast.error(
+                                                                            "Failed to restore kid".to_string(),
+                                                                            ToastOptions::new()
+                                                                                .description(format!("{e}"))
+                                                                                .duration(Duration::from_secs(5)),
+                                                                        );
+                                                                    }
+                                                                    kids_resource.restart();
+                                                                    archived_resource.restart();
+                                                                });
+                                                            },
+                                                            "Restore"
+                                                        }
+                                                        button {
+                                                            style: "font-size: 0.75rem; color: #ef4444; padding: 0.25rem 0.5rem; border-radius: 0.25rem; border: none; cursor: pointer; background: transparent;",
+                                                            onclick: move |_| confirm_delete_id.set(Some(kid_id)),
+                                                            "Delete"
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
                 }
 
                 // ── Aggregation Section ──
@@ -370,7 +718,7 @@ pub fn SettingsPage() -> Element {
                                                             let value = value.clone();
                                                             popover_open.set(false);
                                                             spawn(async move {
-                                                                if let Err(e) = update_granularity(value).await {
+                                                                if let Err(e) = Api::update_granularity(value).await {
                                                                     let toast = consume_toast();
                                                                     toast.error(
                                                                         "Failed to update aggregation".to_string(),
@@ -394,6 +742,116 @@ pub fn SettingsPage() -> Element {
                     }
                 }
 
+                // ── Limits Section ──
+                div { style: "border-radius: 0.75rem; border: 1px solid #e5e7eb; background: white; box-shadow: 0 1px 2px rgba(0,0,0,0.05);",
+                    div { style: "padding: 1.25rem;",
+                        h2 { class: "text-lg font-semibold text-gray-900", "Limits" }
+                        p { class: "text-sm text-gray-400", style: "margin-top: 2px; margin-bottom: 1rem;",
+                            "How many kids, and how long their names can be."
+                        }
+                        div { style: "display: flex; gap: 0.75rem;",
+                            label { style: "flex: 1; font-size: 0.75rem; color: #6b7280;",
+                                "Maximum kids"
+                                input {
+                                    style: "display: block; width: 100%; margin-top: 0.25rem; border-radius: 0.5rem; border: 1px solid #e5e7eb; background: white; padding: 0.375rem 0.75rem; font-size: 0.875rem; outline: none;",
+                                    r#type: "number",
+                                    min: "{MAX_KIDS_RANGE.0}",
+                                    max: "{MAX_KIDS_RANGE.1}",
+                                    value: "{limits.max_kids}",
+                                    onchange: move |e: Event<FormData>| {
+                                        if let Ok(max_kids) = e.value().parse::<u32>() {
+                                            save_limits(KidLimits { max_kids, ..limits });
+                                        }
+                                    },
+                                }
+                            }
+                            label { style: "flex: 1; font-size: 0.75rem; color: #6b7280;",
+                                "Maximum name length"
+                                input {
+                                    style: "display: block; width: 100%; margin-top: 0.25rem; border-radius: 0.5rem; border: 1px solid #e5e7eb; background: white; padding: 0.375rem 0.75rem; font-size: 0.875rem; outline: none;",
+                                    r#type: "number",
+                                    min: "{MAX_NAME_LENGTH_RANGE.0}",
+                                    max: "{MAX_NAME_LENGTH_RANGE.1}",
+                                    value: "{limits.max_name_length}",
+                                    onchange: move |e: Event<FormData>| {
+                                        if let Ok(max_name_length) = e.value().parse::<u32>() {
+                                            save_limits(KidLimits { max_name_length, ..limits });
+                                        }
+                                    },
+                                }
+                            }
+                        }
+                    }
+                }
+
+                // ── Leaderboard Section ──
+                div { style: "border-radius: 0.75rem; border: 1px solid #e5e7eb; background: white; box-shadow: 0 1px 2px rgba(0,0,0,0.05);",
+                    div { style: "padding: 1.25rem; display: flex; align-items: center; justify-content: space-between;",
+                        div {
+                            h2 { class: "text-lg font-semibold text-gray-900", "Leaderboard" }
+                            p { class: "text-sm text-gray-400", style: "margin-top: 2px;",
+                                if leaderboard_enabled {
+                                    "Kids are ranked by score on the home screen."
+                                } else {
+                                    "Kids keep their own order on the home screen."
+                                }
+                            }
+                        }
+                        Button {
+                            variant: if leaderboard_enabled { ButtonVariant::Primary } else { ButtonVariant::Secondary },
+                            onclick: move |_| {
+                                spawn(async move {
+                                    if let Err(e) = Api::update_leaderboard(!leaderboard_enabled).await {
+                                        let toast = consume_toast();
+                                        toast.error(
+                                            "Failed to update leaderboard".to_string(),
+                                            ToastOptions::new()
+                                                .description(format!("{e}"))
+                                                .duration(Duration::from_secs(5)),
+                                        );
+                                    }
+                                    leaderboard.restart();
+                                });
+                            },
+                            if leaderboard_enabled { "On" } else { "Off" }
+                        }
+                    }
+                }
+
+                // ── Backdating Section ──
+                div { style: "border-radius: 0.75rem; border: 1px solid #e5e7eb; background: white; box-shadow: 0 1px 2px rgba(0,0,0,0.05);",
+                    div { style: "padding: 1.25rem; display: flex; align-items: center; justify-content: space-between;",
+                        div {
+                            h2 { class: "text-lg font-semibold text-gray-900", "Backdating" }
+                            p { class: "text-sm text-gray-400", style: "margin-top: 2px;",
+                                if backdate_closed_enabled {
+                                    "Notes can be dated up to {MAX_BACKDATE_DAYS} days back, even into a closed cycle."
+                                } else {
+                                    "Notes can be dated up to {MAX_BACKDATE_DAYS} days back, within the current cycle."
+                                }
+                            }
+                        }
+                        Button {
+                            variant: if backdate_closed_enabled { ButtonVariant::Primary } else { ButtonVariant::Secondary },
+                            onclick: move |_| {
+                                spawn(async move {
+                                    if let Err(e) = Api::update_backdate_closed_cycles(!backdate_closed_enabled).await {
+                                        let toast = consume_toast();
+                                        toast.error(
+                                            "Failed to update backdating".to_string(),
+                                            ToastOptions::new()
+                                                .description(format!("{e}"))
+                                                .duration(Duration::from_secs(5)),
+                                        );
+                                    }
+                                    backdate_closed.restart();
+                                });
+                            },
+                            if backdate_closed_enabled { "On" } else { "Off" }
+                        }
+                    }
+                }
+
                 // ── History Section ──
                 div { style: "border-radius: 0.75rem; border: 1px solid #e5e7eb; background: white; box-shadow: 0 1px 2px rgba(0,0,0,0.05);",
                     div { style: "padding: 1.25rem; display: flex; align-items: center; justify-content: space-between;",
@@ -405,7 +863,470 @@ pub fn SettingsPage() -> Element {
                     }
                 }
 
+                ExportSection {}
+
+                ImportSection {}
+
+                BackupsSection {}
+
+                AuditLogSection {}
+
+                TimeTravelSection {}
+
             }
         }
     }
 }
+
+/// Hands `file` to the browser as a download.
+pub(crate) fn download(file: &ExportFile) {
+    let eval = document::eval(&format!(
+        r#"const bytes = new Uint8Array(await dioxus.recv());
+        const url = URL.createObjectURL(new Blob([bytes], {{ type: "{}" }}));
+        const link = document.createElement("a");
+        link.href = url;
+        link.download = "{}";
+        link.click();
+        setTimeout(() => URL.revokeObjectURL(url), 1000);"#,
+        file.content_type, file.file_name
+    ));
+    let _ = eval.send(&file.bytes);
+}
+
+/// Downloads all household data as JSON or as a zip of CSV files.
+#[component]
+fn ExportSection() -> Element {
+    let mut exporting = use_signal(|| false);
+
+    let export = move |format: ExportFormat| {
+        exporting.set(true);
+        spawn(async move {
+            match Api::export_data(format).await {
+                Ok(file) => download(&file),
+                Err(e) => {
+                    let toast = consume_toast();
+                    toast.error(
+                        "Failed to export data".to_string(),
+                        ToastOptions::new()
+                            .description(format!("{e}"))
+                            .duration(Duration::from_secs(5)),
+                    );
+                }
+            }
+            exporting.set(false);
+        });
+    };
+
+    rsx! {
+        div { style: "border-radius: 0.75rem; border: 1px solid #e5e7eb; background: white; box-shadow: 0 1px 2px rgba(0,0,0,0.05);",
+            div { style: "padding: 1.25rem; display: flex; align-items: center; justify-content: space-between; gap: 1rem;",
+                div {
+                    h2 { class: "text-lg font-semibold text-gray-900", "Export" }
+                    p { class: "text-sm text-gray-400", style: "margin-top: 2px;",
+                        "Kids, notes, settings and cycle totals, as one JSON file or CSV files in a zip."
+                    }
+                }
+                div { style: "display: flex; gap: 0.5rem;",
+                    Button {
+                        variant: ButtonVariant::Secondary,
+                        disabled: exporting(),
+                        onclick: move |_| export(ExportFormat::CsvZip),
+                        "CSV"
+                    }
+                    Button {
+                        variant: ButtonVariant::Secondary,
+                        disabled: exporting(),
+                        onclick: move |_| export(ExportFormat::Json),
+                        "JSON"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Loads a file in the export format. Picking a file shows a dry-run preview;
+/// nothing is written until the import is confirmed.
+#[component]
+fn ImportSection() -> Element {
+    let mut file: Signal<Option<Vec<u8>>> = use_signal(|| None);
+    let mut preview: Signal<Option<ImportPreview>> = use_signal(|| None);
+    let mut busy = use_signal(|| false);
+
+    let mut run = move |dry_run: bool| {
+        let Some(bytes) = file() else {
+            return;
+        };
+        busy.set(true);
+        spawn(async move {
+            match Api::import_data(bytes, dry_run).await {
+                Ok(result) => {
+                    if result.committed {
+                        file.set(None);
+                    }
+                    preview.set(Some(result));
+                }
+                Err(e) => {
+                    let toast = consume_toast();
+                    toast.error(
+                        "Failed to import data".to_string(),
+                        ToastOptions::new()
+                            .description(format!("{e}"))
+                            .duration(Duration::from_secs(5)),
+                    );
+                }
+            }
+            busy.set(false);
+        });
+    };
+
+    rsx! {
+        div { style: "border-radius: 0.75rem; border: 1px solid #e5e7eb; background: white; box-shadow: 0 1px 2px rgba(0,0,0,0.05);",
+            div { style: "padding: 1.25rem;",
+                h2 { class: "text-lg font-semibold text-gray-900", "Import" }
+                p { class: "text-sm text-gray-400", style: "margin-top: 2px; margin-bottom: 1rem;",
+                    "Add kids and notes from a JSON or CSV export. Kids are matched by name; settings are left as they are."
+                }
+                input {
+                    style: "font-size: 0.875rem;",
+                    r#type: "file",
+                    accept: ".json,.zip",
+                    onchange: move |e: Event<FormData>| async move {
+                        let Some(picked) = e.files().into_iter().next() else {
+                            return;
+                        };
+                        preview.set(None);
+                        if picked.size() as usize > MAX_IMPORT_BYTES {
+                            let toast = consume_toast();
+                            toast.error(
+                                "Failed to import data".to_string(),
+                                ToastOptions::new()
+                                    .description("File too large (max 20 MB)".to_string())
+                                    .duration(Duration::from_secs(5)),
+                            );
+                            return;
+                        }
+                        match picked.read_bytes().await {
+                            Ok(bytes) => {
+                                file.set(Some(bytes.to_vec()));
+                                run(true);
+                            }
+                            Err(e) => {
+                                let toast = consume_toast();
+                                toast.error(
+                                    "Failed to read file".to_string(),
+                                    ToastOptions::new()
+                                        .description(e.to_string())
+                                        .duration(Duration::from_secs(5)),
+                                );
+                            }
+                        }
+                    },
+                }
+                if let Some(result) = preview() {
+                    div { style: "margin-top: 1rem; font-size: 0.875rem;",
+                        if !result.errors.is_empty() {
+                            p { class: "text-red-500 font-medium", "The file can't be imported:" }
+                            ul { style: "margin-top: 0.25rem; padding-left: 1.25rem; list-style: disc;",
+                                for error in result.errors.iter() {
+                                    li { class: "text-red-500", "{error}" }
+                                }
+                            }
+                        } else {
+                            p { class: "font-medium text-gray-900",
+                                if result.committed { "Imported:" } else { "Preview:" }
+                            }
+                            ul { style: "margin-top: 0.25rem; padding-left: 1.25rem; list-style: disc;", class: "text-gray-500",
+                                li { "{result.kids_added.len()} new kids" }
+                                li { "{result.kids_matched.len()} existing kids matched by name" }
+                                li { "{result.notes_added} notes added" }
+                                if result.notes_skipped > 0 {
+                                    li { "{result.notes_skipped} notes already imported, skipped" }
+                                }
+                            }
+                            if !result.committed {
+                                div { style: "margin-top: 0.75rem;",
+                                    Button {
+                                        variant: ButtonVariant::Primary,
+                                        disabled: busy(),
+                                        onclick: move |_| run(false),
+                                        "Import"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Backup target and stored backups, with manual backup and restore.
+#[component]
+fn BackupsSection() -> Element {
+    let mut status = use_resource(Api::get_backup_status);
+    let mut busy = use_signal(|| false);
+
+    let fail = |title: &str, e: ServerFnError| {
+        let toast = consume_toast();
+        toast.error(
+            title.to_string(),
+            ToastOptions::new()
+                .description(format!("{e}"))
+                .duration(Duration::from_secs(5)),
+        );
+    };
+
+    rsx! {
+        div { style: "border-radius: 0.75rem; border: 1px solid #e5e7eb; background: white; box-shadow: 0 1px 2px rgba(0,0,0,0.05);",
+            div { style: "padding: 1.25rem;",
+                match &*status.read() {
+                    Some(Ok(status)) => match &status.target {
+                        Some(target) => rsx! {
+                            div { style: "display: flex; align-items: center; justify-content: space-between; gap: 1rem;",
+                                div {
+                                    h2 { class: "text-lg font-semibold text-gray-900", "Backups" }
+                                    p { class: "text-sm text-gray-400", style: "margin-top: 2px;",
+                                        "Every {status.interval_hours} hours to {target}, keeping the last {status.keep}."
+                                    }
+                                }
+                                Button {
+                                    variant: ButtonVariant::Secondary,
+                                    disabled: busy(),
+                                    onclick: move |_| {
+                                        busy.set(true);
+                                        spawn(async move {
+                                            if let Err(e) = Api::back_up_now().await {
+                                                fail("Failed to back up", e);
+                                            }
+                                            busy.set(false);
+                                            status.restart();
+                                        });
+                                    },
+                                    "Back up now"
+                                }
+                            }
+                            div { style: "display: flex; flex-direction: column; margin-top: 0.75rem;",
+                                for backup in status.backups.iter().cloned() {
+                                    div {
+                                        key: "{backup.name}",
+                                        style: "display: flex; align-items: center; justify-content: space-between; padding: 0.5rem 0; border-top: 1px solid #f3f4f6; font-size: 0.875rem;",
+                                        span { class: "text-gray-900",
+                                            {backup.created_at.format("%Y-%m-%d %H:%M").to_string()}
+                                            " UTC"
+                                        }
+                                        div { style: "display: flex; align-items: center; gap: 0.75rem;",
+                                            span { class: "text-gray-400", "{backup.size / 1024 + 1} KB" }
+                                            Button {
+                                                variant: ButtonVariant::Ghost,
+                                                disabled: busy(),
+                                                onclick: move |_| {
+                                                    let name = backup.name.clone();
+                                                    busy.set(true);
+                                                    spawn(async move {
+                                                        if let Err(e) = Api::restore_backup(name).await {
+                                                            fail("Failed to restore backup", e);
+                                                        }
+                                                        busy.set(false);
+                                                    });
+                                                },
+                                                "Restore"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                            p { class: "text-sm text-gray-400", style: "margin-top: 0.5rem;",
+                                "Restoring only works on an empty database, e.g. a new deployment."
+                            }
+                        },
+                        None => rsx! {
+                            h2 { class: "text-lg font-semibold text-gray-900", "Backups" }
+                            p { class: "text-sm text-gray-400", style: "margin-top: 2px;",
+                                "Off. Set BACKUP_DIR or BACKUP_S3_BUCKET on the server to turn them on."
+                            }
+                        },
+                    },
+                    Some(Err(e)) => rsx! {
+                        p { class: "text-sm text-red-500", "Failed to load backups: {e}" }
+                    },
+                    None => rsx! {
+                        p { class: "text-sm text-gray-400", "Loading…" }
+                    },
+                }
+            }
+        }
+    }
+}
+
+/// Recent changes from the audit log, filterable by action and day.
+#[component]
+fn AuditLogSection() -> Element {
+    let mut action: Signal<Option<AuditAction>> = use_signal(|| None);
+    let mut from = use_signal(|| String::new());
+    let mut to = use_signal(|| String::new());
+
+    let parse_day = |value: String| chrono::NaiveDate::parse_from_str(&value, "%Y-%m-%d").ok();
+    let mut entries =
+        use_resource(move || Api::list_audit_log(action(), parse_day(from()), parse_day(to())));
+
+    // Every change lands in the log, so any event means there is something new
+    use_future(move || async move {
+        let Ok(mut changes) = subscribe_changes().await else {
+            return;
+        };
+        while let Some(Ok(_)) = changes.recv().await {
+            entries.restart();
+        }
+    });
+
+    let field_style = "border-radius: 0.5rem; border: 1px solid #e5e7eb; background: white; padding: 0.375rem 0.75rem; font-size: 0.875rem; outline: none;";
+
+    rsx! {
+        div { style: "border-radius: 0.75rem; border: 1px solid #e5e7eb; background: white; box-shadow: 0 1px 2px rgba(0,0,0,0.05);",
+            div { style: "padding: 1.25rem;",
+                h2 { class: "text-lg font-semibold text-gray-900", "Audit log" }
+                p { class: "text-sm text-gray-400", style: "margin-top: 2px; margin-bottom: 1rem;",
+                    "Every change to kids, notes and settings. Entries cannot be edited or removed."
+                }
+                div { style: "display: flex; flex-wrap: wrap; gap: 0.5rem; margin-bottom: 1rem;",
+                    select {
+                        style: "{field_style}",
+                        onchange: move |e: Event<FormData>| action.set(AuditAction::parse(&e.value())),
+                        option { value: "", selected: action().is_none(), "All actions" }
+                        for a in AuditAction::ALL {
+                            option { value: a.as_str(), selected: action() == Some(a), "{a.label()}" }
+                        }
+                    }
+                    input {
+                        style: "{field_style}",
+                        r#type: "date",
+                        value: "{from}",
+                        oninput: move |e: Event<FormData>| from.set(e.value()),
+                    }
+                    input {
+                        style: "{field_style}",
+                        r#type: "date",
+                        value: "{to}",
+                        oninput: move |e: Event<FormData>| to.set(e.value()),
+                    }
+                }
+                match &*entries.read() {
+                    Some(Ok(entries)) if entries.is_empty() => rsx! {
+                        p { class: "text-sm text-gray-400", "No matching entries." }
+                    },
+                    Some(Ok(entries)) => rsx! {
+                        div { style: "display: flex; flex-direction: column; max-height: 24rem; overflow-y: auto;",
+                            for (entry, time) in entries.iter().map(|e| (e.clone(), e.created_at.format("%Y-%m-%d %H:%M").to_string())) {
+                                div {
+                                    key: "{entry.id}",
+                                    style: "padding: 0.5rem 0; border-top: 1px solid #f3f4f6; font-size: 0.875rem;",
+                                    div { style: "display: flex; justify-content: space-between; gap: 0.5rem;",
+                                        span { class: "font-medium text-gray-900",
+                                            "{entry.action.label()}"
+                                            if let Some(name) = &entry.kid_name {
+                                                " · {name}"
+                                            }
+                                        }
+                                        span { class: "text-gray-400",
+                                            "{time} UTC · {entry.actor}"
+                                        }
+                                    }
+                                    match (&entry.before, &entry.after) {
+                                        (Some(before), Some(after)) => rsx! {
+                                            div { class: "text-gray-500", "{before} → {after}" }
+                                        },
+                                        (None, Some(after)) => rsx! {
+                                            div { class: "text-gray-500", "{after}" }
+                                        },
+                                        (Some(before), None) => rsx! {
+                                            div { class: "text-gray-500", "was {before}" }
+                                        },
+                                        (None, None) => rsx! {},
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    Some(Err(e)) => rsx! {
+                        p { class: "text-sm text-red-500", "Failed to load the audit log: {e}" }
+                    },
+                    None => rsx! {
+                        p { class: "text-sm text-gray-400", "Loading…" }
+                    },
+                }
+            }
+        }
+    }
+}
+
+/// Debug builds only: moves the backend clock, to check cycle rollovers by hand.
+#[cfg(debug_assertions)]
+#[component]
+fn TimeTravelSection() -> Element {
+    let mut backend_time = use_resource(Api::get_backend_time);
+    let mut target = use_signal(|| String::new());
+
+    let travel = move |to: Option<chrono::NaiveDateTime>| {
+        spawn(async move {
+            if let Err(e) = Api::travel_to(to).await {
+                let toast = consume_toast();
+                toast.error(
+                    "Failed to time travel".to_string(),
+                    ToastOptions::new()
+                        .description(format!("{e}"))
+                        .duration(Duration::from_secs(5)),
+                );
+            }
+            backend_time.restart();
+        });
+    };
+
+    let now_label = match &*backend_time.read() {
+        Some(Ok(now)) => now.format("%Y-%m-%d %H:%M").to_string(),
+        _ => "…".to_string(),
+    };
+
+    rsx! {
+        div { style: "border-radius: 0.75rem; border: 1px dashed #f59e0b; background: #fffbeb;",
+            div { style: "padding: 1.25rem;",
+                h2 { class: "text-lg font-semibold text-gray-900", "Time travel" }
+                p { class: "text-sm text-gray-400", style: "margin-top: 2px; margin-bottom: 1rem;",
+                    "Debug builds only. Backend time: {now_label} UTC"
+                }
+                div { style: "display: flex; gap: 0.5rem; align-items: center;",
+                    input {
+                        style: "flex: 1; border-radius: 0.5rem; border: 1px solid #e5e7eb; background: white; padding: 0.375rem 0.75rem; font-size: 0.875rem; outline: none;",
+                        r#type: "datetime-local",
+                        value: "{target}",
+                        oninput: move |e: Event<FormData>| target.set(e.value()),
+                    }
+                    Button {
+                        variant: ButtonVariant::Primary,
+                        onclick: move |_| {
+                            if let Ok(to) = chrono::NaiveDateTime::parse_from_str(&target(), "%Y-%m-%dT%H:%M") {
+                                travel(Some(to));
+                            }
+                        },
+                        "Go"
+                    }
+                    Button {
+                        variant: ButtonVariant::Secondary,
+                        onclick: move |_| {
+                            target.set(String::new());
+                            travel(None);
+                        },
+                        "Back to now"
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(debug_assertions))]
+#[component]
+fn TimeTravelSection() -> Element {
+    VNode::empty()
+}
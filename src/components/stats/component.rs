@@ -0,0 +1,509 @@
+use crate::backend::api::{Api, Backend};
+use crate::components::button::*;
+use crate::components::kid_card::avatar_color;
+use crate::components::settings::download;
+use crate::models::{
+    competition_ranks, CycleTotalsResponse, ExportFile, HeatmapCell, KidNoteRatio, ReportFormat,
+    ReportPeriod,
+};
+use crate::Route;
+use dioxus::prelude::*;
+use dioxus_primitives::toast::{consume_toast, ToastOptions};
+use std::time::Duration;
+
+const CYCLE_OPTIONS: &[u32] = &[4, 8, 12];
+
+const WEEKDAYS: &[&str] = &["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+#[component]
+pub fn StatsPage() -> Element {
+    let mut cycles = use_signal(|| 4u32);
+
+    let totals = use_resource(move || {
+        let n = cycles();
+        async move { Api::get_cycle_totals(n).await }
+    });
+    let ratios = use_resource(Api::get_note_ratios);
+    // Bucket by the device's current offset; notes from the other side of a DST
+    // change land an hour off, which the heatmap's resolution shrugs off.
+    let heatmap = use_resource(|| {
+        let offset = chrono::Local::now().offset().local_minus_utc() / 60;
+        Api::get_activity_heatmap(offset)
+    });
+    let leaderboard = use_resource(Api::get_leaderboard);
+
+    rsx! {
+        // ── Header ──
+        div { class: "mb-8 flex items-center gap-4",
+            Link {
+                to: Route::MainView,
+                style: "display: flex; align-items: center; justify-content: center; width: 2rem; height: 2rem; border-radius: 50%; color: #9ca3af; transition: all 0.15s;",
+                svg {
+                    xmlns: "http://www.w3.org/2000/svg",
+                    fill: "none",
+                    view_box: "0 0 24 24",
+                    stroke_width: "2",
+                    stroke: "currentColor",
+                    class: "h-5 w-5",
+                    path {
+                        stroke_linecap: "round",
+                        stroke_linejoin: "round",
+                        d: "M15.75 19.5 8.25 12l7.5-7.5",
+                    }
+                }
+            }
+            h1 { class: "text-2xl font-semibold text-gray-900", "Stats" }
+        }
+
+        div { class: "space-y-6",
+
+            // ── Totals per cycle ──
+            div { style: "border-radius: 0.75rem; border: 1px solid #e5e7eb; background: white; box-shadow: 0 1px 2px rgba(0,0,0,0.05); padding: 1.25rem;",
+                div { style: "display: flex; align-items: center; justify-content: space-between; margin-bottom: 1rem;",
+                    div {
+                        h2 { class: "text-lg font-semibold text-gray-900", "Totals per cycle" }
+                        p { class: "text-sm text-gray-400", style: "margin-top: 2px;", "How each kid did over the last cycles." }
+                    }
+                    div { style: "display: flex; gap: 0.25rem;",
+                        for n in CYCLE_OPTIONS.iter().copied() {
+                            button {
+                                style: if cycles() == n { "padding: 0.25rem 0.5rem; border-radius: 0.375rem; border: none; font-size: 0.75rem; font-weight: 600; cursor: pointer; background-color: #111; color: #fff;" } else { "padding: 0.25rem 0.5rem; border-radius: 0.375rem; border: none; font-size: 0.75rem; font-weight: 500; cursor: pointer; background-color: #f3f4f6; color: #6b7280;" },
+                                onclick: move |_| cycles.set(n),
+                                "{n}"
+                            }
+                        }
+                    }
+                }
+                match &*totals.read() {
+                    Some(Ok(data)) => rsx! {
+                        CycleTotalsChart { data: data.clone() }
+                    },
+                    Some(Err(_)) => rsx! {
+                        p { class: "text-sm", style: "color: #ef4444;", "Failed to load totals." }
+                    },
+                    None => rsx! {
+                        p { class: "text-sm text-gray-400", "Loading..." }
+                    },
+                }
+            }
+
+            // ── Leaderboard (opt-in) ──
+            if let (Some(Ok(true)), Some(Ok(data))) = (&*leaderboard.read(), &*totals.read()) {
+                div { style: "border-radius: 0.75rem; border: 1px solid #e5e7eb; background: white; box-shadow: 0 1px 2px rgba(0,0,0,0.05); padding: 1.25rem;",
+                    div { style: "margin-bottom: 1rem;",
+                        h2 { class: "text-lg font-semibold text-gray-900", "Leaderboard" }
+                        p { class: "text-sm text-gray-400", style: "margin-top: 2px;", "Who came out on top in each cycle." }
+                    }
+                    Leaderboard { data: data.clone() }
+                }
+            }
+
+            // ── Positive vs negative ──
+            div { style: "border-radius: 0.75rem; border: 1px solid #e5e7eb; background: white; box-shadow: 0 1px 2px rgba(0,0,0,0.05); padding: 1.25rem;",
+                div { style: "margin-bottom: 1rem;",
+                    h2 { class: "text-lg font-semibold text-gray-900", "Positive vs. negative" }
+                    p { class: "text-sm text-gray-400", style: "margin-top: 2px;", "Share of +1 and -1 notes, all time." }
+                }
+                match &*ratios.read() {
+                    Some(Ok(data)) => rsx! {
+                        RatioChart { ratios: data.clone() }
+                    },
+                    Some(Err(_)) => rsx! {
+                        p { class: "text-sm", style: "color: #ef4444;", "Failed to load ratios." }
+                    },
+                    None => rsx! {
+                        p { class: "text-sm text-gray-400", "Loading..." }
+                    },
+                }
+            }
+
+            // ── Time of day ──
+            div { style: "border-radius: 0.75rem; border: 1px solid #e5e7eb; background: white; box-shadow: 0 1px 2px rgba(0,0,0,0.05); padding: 1.25rem;",
+                div { style: "margin-bottom: 1rem;",
+                    h2 { class: "text-lg font-semibold text-gray-900", "When notes happen" }
+                    p { class: "text-sm text-gray-400", style: "margin-top: 2px;", "Notes per weekday and hour (UTC)." }
+                }
+                match &*heatmap.read() {
+                    Some(Ok(data)) => rsx! {
+                        ActivityHeatmap { cells: data.clone() }
+                    },
+                    Some(Err(_)) => rsx! {
+                        p { class: "text-sm", style: "color: #ef4444;", "Failed to load activity." }
+                    },
+                    None => rsx! {
+                        p { class: "text-sm text-gray-400", "Loading..." }
+                    },
+                }
+            }
+
+            // ── Reports ──
+            ReportSection {}
+        }
+    }
+}
+
+/// How many weeks or months back a report can be picked from.
+const REPORT_CYCLES: u32 = 6;
+
+/// "This week", "Last month", "3 weeks ago"...
+fn report_cycle_label(period: ReportPeriod, cycles_ago: u32) -> String {
+    let unit = match period {
+        ReportPeriod::Week => "week",
+        ReportPeriod::Month => "month",
+    };
+    match cycles_ago {
+        0 => format!("This {unit}"),
+        1 => format!("Last {unit}"),
+        n => format!("{n} {unit}s ago"),
+    }
+}
+
+/// Opens an HTML report in a new tab, ready to print. Falls back to a download
+/// when the browser blocks the tab.
+fn open_page(file: &ExportFile) {
+    let eval = document::eval(&format!(
+        r#"const bytes = new Uint8Array(await dioxus.recv());
+        const url = URL.createObjectURL(new Blob([bytes], {{ type: "{}" }}));
+        if (!window.open(url, "_blank")) {{
+            const link = document.createElement("a");
+            link.href = url;
+            link.download = "{}";
+            link.click();
+        }}
+        setTimeout(() => URL.revokeObjectURL(url), 60000);"#,
+        file.content_type, file.file_name
+    ));
+    let _ = eval.send(&file.bytes);
+}
+
+/// Picks a kid and a week or month, then opens a printable page or downloads a PDF.
+#[component]
+fn ReportSection() -> Element {
+    let kids = use_resource(Api::list_kids);
+    let mut picked: Signal<Option<u32>> = use_signal(|| None);
+    let mut period = use_signal(|| ReportPeriod::Week);
+    let mut cycles_ago = use_signal(|| 0u32);
+    let mut busy = use_signal(|| false);
+
+    let list = match &*kids.read() {
+        Some(Ok(list)) => list.clone(),
+        _ => Vec::new(),
+    };
+    // The first kid until another one is picked
+    let kid_id = picked().or_else(|| list.first().map(|k| k.id));
+
+    let generate = move |format: ReportFormat| {
+        let Some(kid_id) = kid_id else {
+            return;
+        };
+        busy.set(true);
+        let offset = chrono::Local::now().offset().local_minus_utc() / 60;
+        spawn(async move {
+            match Api::kid_report(kid_id, period(), cycles_ago(), offset, format).await {
+                Ok(file) if format == ReportFormat::Html => open_page(&file),
+                Ok(file) => download(&file),
+                Err(e) => {
+                    let toast = consume_toast();
+                    toast.error(
+                        "Failed to build the report".to_string(),
+                        ToastOptions::new()
+                            .description(format!("{e}"))
+                            .duration(Duration::from_secs(5)),
+                    );
+                }
+            }
+            busy.set(false);
+        });
+    };
+
+    let field_style = "border-radius: 0.5rem; border: 1px solid #e5e7eb; background: white; padding: 0.375rem 0.75rem; font-size: 0.875rem; outline: none;";
+
+    rsx! {
+        div { style: "border-radius: 0.75rem; border: 1px solid #e5e7eb; background: white; box-shadow: 0 1px 2px rgba(0,0,0,0.05); padding: 1.25rem;",
+            div { style: "display: flex; align-items: center; justify-content: space-between; margin-bottom: 1rem;",
+                div {
+                    h2 { class: "text-lg font-semibold text-gray-900", "Reports" }
+                    p { class: "text-sm text-gray-400", style: "margin-top: 2px;", "A kid's week or month to print or share." }
+                }
+                div { style: "display: flex; gap: 0.25rem;",
+                    for (p, label) in [(ReportPeriod::Week, "Week"), (ReportPeriod::Month, "Month")] {
+                        button {
+                            style: if period() == p { "padding: 0.25rem 0.5rem; border-radius: 0.375rem; border: none; font-size: 0.75rem; font-weight: 600; cursor: pointer; background-color: #111; color: #fff;" } else { "padding: 0.25rem 0.5rem; border-radius: 0.375rem; border: none; font-size: 0.75rem; font-weight: 500; cursor: pointer; background-color: #f3f4f6; color: #6b7280;" },
+                            onclick: move |_| period.set(p),
+                            "{label}"
+                        }
+                    }
+                }
+            }
+            if list.is_empty() {
+                p { class: "text-sm text-gray-400", "Add a kid to build reports." }
+            } else {
+                div { style: "display: flex; flex-wrap: wrap; align-items: center; gap: 0.5rem;",
+                    select {
+                        style: "{field_style}",
+                        onchange: move |e: Event<FormData>| picked.set(e.value().parse().ok()),
+                        for kid in list.iter() {
+                            option { value: "{kid.id}", selected: kid_id == Some(kid.id), "{kid.name}" }
+                        }
+                    }
+                    select {
+                        style: "{field_style}",
+                        onchange: move |e: Event<FormData>| cycles_ago.set(e.value().parse().unwrap_or(0)),
+                        for n in 0..REPORT_CYCLES {
+                            option { value: "{n}", selected: cycles_ago() == n, "{report_cycle_label(period(), n)}" }
+                        }
+                    }
+                    div { style: "display: flex; gap: 0.5rem; margin-left: auto;",
+                        Button {
+                            variant: ButtonVariant::Secondary,
+                            disabled: busy(),
+                            onclick: move |_| generate(ReportFormat::Html),
+                            "Print"
+                        }
+                        Button {
+                            variant: ButtonVariant::Secondary,
+                            disabled: busy(),
+                            onclick: move |_| generate(ReportFormat::Pdf),
+                            "PDF"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Grouped bar chart: one group per cycle, one bar per kid. Negative totals grow
+/// downwards from the zero line.
+#[component]
+fn CycleTotalsChart(data: CycleTotalsResponse) -> Element {
+    if data.kids.is_empty() {
+        return rsx! {
+            p { class: "text-sm text-gray-400", "No kids yet." }
+        };
+    }
+
+    let (width, height) = (480.0_f32, 200.0_f32);
+    let (left, top, plot_w, plot_h) = (8.0_f32, 8.0_f32, 464.0_f32, 164.0_f32);
+
+    let all = data.kids.iter().flat_map(|k| k.totals.iter().copied());
+    let max_pos = all.clone().max().unwrap_or(0).max(0) as f32;
+    let max_neg = (-all.min().unwrap_or(0)).max(0) as f32;
+    let range = (max_pos + max_neg).max(1.0);
+    let zero_y = top + plot_h * max_pos / range;
+
+    let periods = data.periods.len().max(1);
+    let group_w = plot_w / periods as f32;
+    let bar_w = group_w * 0.8 / data.kids.len() as f32;
+    let label_every = periods.div_ceil(8);
+
+    let view_box = format!("0 0 {width} {height}");
+
+    rsx! {
+        svg {
+            xmlns: "http://www.w3.org/2000/svg",
+            view_box: "{view_box}",
+            style: "width: 100%; height: auto;",
+            line {
+                x1: "{left}",
+                y1: "{zero_y}",
+                x2: "{left + plot_w}",
+                y2: "{zero_y}",
+                stroke: "#e5e7eb",
+                stroke_width: "1",
+            }
+            for (k, kid) in data.kids.iter().enumerate() {
+                for (i, total) in kid.totals.iter().copied().enumerate() {
+                    {
+                        let bar_h = plot_h * (total.unsigned_abs() as f32) / range;
+                        let x = left + i as f32 * group_w + group_w * 0.1 + k as f32 * bar_w;
+                        let y = if total >= 0 { zero_y - bar_h } else { zero_y };
+                        let color = avatar_color(kid.color.as_deref(), &kid.name);
+                        rsx! {
+                            rect {
+                                x: "{x}",
+                                y: "{y}",
+                                width: "{bar_w}",
+                                height: "{bar_h}",
+                                rx: "1.5",
+                                fill: "{color}",
+                            }
+                        }
+                    }
+                }
+            }
+            for (i, period) in data.periods.iter().enumerate() {
+                if i % label_every == 0 || i + 1 == data.periods.len() {
+                    text {
+                        x: "{left + (i as f32 + 0.5) * group_w}",
+                        y: "{height - 8.0}",
+                        font_size: "10",
+                        fill: "#9ca3af",
+                        text_anchor: "middle",
+                        "{period}"
+                    }
+                }
+            }
+        }
+
+        // Legend
+        div { style: "display: flex; flex-wrap: wrap; gap: 0.75rem; margin-top: 0.75rem;",
+            for kid in data.kids.iter() {
+                div { style: "display: flex; align-items: center; gap: 0.375rem; font-size: 0.75rem; color: #6b7280;",
+                    span { style: "width: 0.625rem; height: 0.625rem; border-radius: 2px; background-color: {avatar_color(kid.color.as_deref(), &kid.name)};" }
+                    "{kid.name}"
+                }
+            }
+        }
+    }
+}
+
+/// Ranked kids per cycle, most recent cycle first.
+#[component]
+fn Leaderboard(data: CycleTotalsResponse) -> Element {
+    if data.kids.is_empty() {
+        return rsx! {
+            p { class: "text-sm text-gray-400", "No kids yet." }
+        };
+    }
+
+    rsx! {
+        div { style: "display: flex; flex-direction: column;",
+            for (i, period) in data.periods.iter().enumerate().rev() {
+                {
+                    let mut standings: Vec<(&str, i32)> = data
+                        .kids
+                        .iter()
+                        .map(|k| (k.name.as_str(), k.totals.get(i).copied().unwrap_or(0)))
+                        .collect();
+                    standings.sort_by(|a, b| b.1.cmp(&a.1));
+                    let scores: Vec<i32> = standings.iter().map(|(_, total)| *total).collect();
+                    let ranks = competition_ranks(&scores);
+                    rsx! {
+                        div { style: "display: flex; align-items: baseline; gap: 0.75rem; padding: 0.5rem 0; border-top: 1px solid #f3f4f6;",
+                            span { style: "width: 5.5rem; flex-shrink: 0; font-size: 0.75rem; font-weight: 500; color: #9ca3af;",
+                                "{period}"
+                            }
+                            div { style: "display: flex; flex-wrap: wrap; gap: 0.25rem 0.75rem; font-size: 0.8125rem; color: #374151;",
+                                for ((name, total), rank) in standings.iter().zip(ranks) {
+                                    span {
+                                        span { style: "font-weight: 600; color: #6b7280;", "{rank}. " }
+                                        "{name} "
+                                        span { style: "color: #9ca3af;", "({total})" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// One stacked horizontal bar per kid: green for positive notes, red for negative ones.
+#[component]
+fn RatioChart(ratios: Vec<KidNoteRatio>) -> Element {
+    if ratios.is_empty() {
+        return rsx! {
+            p { class: "text-sm text-gray-400", "No kids yet." }
+        };
+    }
+
+    rsx! {
+        div { style: "display: flex; flex-direction: column; gap: 0.625rem;",
+            for ratio in ratios.iter() {
+                {
+                    let total = ratio.positive + ratio.negative;
+                    let positive_w = if total == 0 { 0.0 } else { 100.0 * ratio.positive as f32 / total as f32 };
+                    rsx! {
+                        div { style: "display: flex; align-items: center; gap: 0.75rem;",
+                            span { style: "width: 5rem; font-size: 0.8125rem; font-weight: 500; color: #374151; overflow: hidden; text-overflow: ellipsis; white-space: nowrap;",
+                                "{ratio.name}"
+                            }
+                            svg {
+                                xmlns: "http://www.w3.org/2000/svg",
+                                view_box: "0 0 100 8",
+                                preserve_aspect_ratio: "none",
+                                style: "flex: 1; height: 0.625rem; border-radius: 9999px; overflow: hidden;",
+                                rect { x: "0", y: "0", width: "100", height: "8", fill: "#f3f4f6" }
+                                if total > 0 {
+                                    rect { x: "0", y: "0", width: "{positive_w}", height: "8", fill: "#22c55e" }
+                                    rect { x: "{positive_w}", y: "0", width: "{100.0 - positive_w}", height: "8", fill: "#ef4444" }
+                                }
+                            }
+                            span { style: "width: 4.5rem; text-align: right; font-size: 0.75rem; color: #9ca3af;",
+                                "+{ratio.positive} / -{ratio.negative}"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Weekday × hour grid shaded by the number of notes logged in each slot.
+#[component]
+fn ActivityHeatmap(cells: Vec<HeatmapCell>) -> Element {
+    let (cell, gap, left, top) = (17.0_f32, 2.0_f32, 30.0_f32, 4.0_f32);
+    let width = left + 24.0 * (cell + gap);
+    let height = top + 7.0 * (cell + gap) + 14.0;
+    let max = cells.iter().map(|c| c.count).max().unwrap_or(0).max(1) as f32;
+
+    let mut grid = [[0u32; 24]; 7];
+    for c in cells.iter() {
+        if c.weekday < 7 && c.hour < 24 {
+            grid[c.weekday as usize][c.hour as usize] = c.count;
+        }
+    }
+
+    let view_box = format!("0 0 {width} {height}");
+
+    rsx! {
+        svg {
+            xmlns: "http://www.w3.org/2000/svg",
+            view_box: "{view_box}",
+            style: "width: 100%; height: auto;",
+            for (d, day) in WEEKDAYS.iter().enumerate() {
+                text {
+                    x: "0",
+                    y: "{top + d as f32 * (cell + gap) + cell * 0.75}",
+                    font_size: "9",
+                    fill: "#9ca3af",
+                    "{day}"
+                }
+                for h in 0..24usize {
+                    {
+                        let count = grid[d][h];
+                        let (fill, opacity) = if count == 0 {
+                            ("#f3f4f6", 1.0)
+                        } else {
+                            ("#6366f1", (count as f32 / max).max(0.15))
+                        };
+                        rsx! {
+                            rect {
+                                x: "{left + h as f32 * (cell + gap)}",
+                                y: "{top + d as f32 * (cell + gap)}",
+                                width: "{cell}",
+                                height: "{cell}",
+                                rx: "3",
+                                fill: "{fill}",
+                                fill_opacity: "{opacity}",
+                            }
+                        }
+                    }
+                }
+            }
+            for h in (0..24usize).step_by(6) {
+                text {
+                    x: "{left + h as f32 * (cell + gap)}",
+                    y: "{height - 2.0}",
+                    font_size: "9",
+                    fill: "#9ca3af",
+                    "{h}h"
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,4 @@
+//! The backend and the types shared with the UI, used by the app (`main.rs`) and by
+//! the `hot_dog-admin` tool.
+pub mod backend;
+pub mod models;
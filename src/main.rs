@@ -1,43 +1,77 @@
-mod backend;
 mod components;
-mod models;
 mod notica_component;
+mod offline;
 
 use dioxus::prelude::*;
+use hot_dog::{backend, models};
 
 use components::about::AboutPage;
 use components::settings::SettingsPage;
+use components::stats::StatsPage;
 use components::toast::ToastProvider;
 use notica_component::NoticaApp;
 
 fn main() {
-    dioxus::launch(|| {
-        rsx! {
-            document::Stylesheet {
-                // Urls are relative to your Cargo.toml file
-                href: asset!("/assets/tailwind.css"),
-            }
-            document::Stylesheet { href: asset!("/assets/dx-components-theme.css") }
-            ToastProvider { Router::<Route> {} }
-        }
+    #[cfg(not(feature = "server"))]
+    dioxus::launch(App);
+
+    // The server also serves uploaded avatars and the PWA files next to the app
+    // and its server functions, and takes scheduled backups when configured
+    #[cfg(feature = "server")]
+    dioxus::serve(|| async move {
+        backend::backup::start_schedule();
+        Ok(dioxus::server::router(App)
+            .merge(backend::avatars::routes())
+            .merge(backend::pwa::routes()))
     });
 }
 
+#[component]
+fn App() -> Element {
+    use_effect(offline::register_service_worker);
+
+    rsx! {
+        document::Link { rel: "manifest", href: "/manifest.webmanifest" }
+        document::Link { rel: "apple-touch-icon", href: "/icon.svg" }
+        document::Meta { name: "theme-color", content: "#111111" }
+        document::Stylesheet {
+            // Urls are relative to your Cargo.toml file
+            href: asset!("/assets/tailwind.css"),
+        }
+        document::Stylesheet { href: asset!("/assets/dx-components-theme.css") }
+        ToastProvider { Router::<Route> {} }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Routable)]
 pub enum Route {
     #[route("/")]
     MainView,
     #[route("/settings")]
     SettingsView,
+    #[route("/stats")]
+    StatsView,
     #[route("/about")]
     AboutView,
 }
 
+/// Shown while a page waits for its server data. During server rendering the
+/// data is awaited before the HTML is sent, so this mostly appears on client navigation.
+fn loading_card(_: SuspenseContext) -> Element {
+    rsx! {
+        div { style: "border: 1px solid #e5e7eb; border-radius: 0.75rem; background: #fff; box-shadow: 0 1px 2px rgba(0,0,0,0.05); padding: 3rem 1.5rem; text-align: center;",
+            p { style: "font-size: 0.875rem; color: #9ca3af;", "Loading..." }
+        }
+    }
+}
+
 #[component]
 fn MainView() -> Element {
     rsx! {
         div { style: "min-height: 100vh; background-color: #f3f4f6;",
-            div { style: "max-width: 520px; margin: 0 auto; padding: 2rem 1rem;", NoticaApp {} }
+            div { style: "max-width: 520px; margin: 0 auto; padding: 2rem 1rem;",
+                SuspenseBoundary { fallback: loading_card, NoticaApp {} }
+            }
         }
     }
 }
@@ -46,7 +80,18 @@ fn MainView() -> Element {
 fn SettingsView() -> Element {
     rsx! {
         div { style: "min-height: 100vh; background-color: #f3f4f6;",
-            div { style: "max-width: 520px; margin: 0 auto; padding: 2rem 1rem;", SettingsPage {} }
+            div { style: "max-width: 520px; margin: 0 auto; padding: 2rem 1rem;",
+                SuspenseBoundary { fallback: loading_card, SettingsPage {} }
+            }
+        }
+    }
+}
+
+#[component]
+fn StatsView() -> Element {
+    rsx! {
+        div { style: "min-height: 100vh; background-color: #f3f4f6;",
+            div { style: "max-width: 520px; margin: 0 auto; padding: 2rem 1rem;", StatsPage {} }
         }
     }
 }
@@ -1,8 +1,8 @@
-use chrono::NaiveDateTime;
+use chrono::{NaiveDate, NaiveDateTime};
 use dioxus::fullstack::serde::Serialize;
 use serde::Deserialize;
 
-#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum CountAggregation {
     /// month, year
     Monthly(u32, u32),
@@ -34,6 +34,16 @@ impl CountAggregation {
         }
     }
 
+    /// Returns the footer label for a closed cycle (e.g. "Past month").
+    pub fn past_unit_label(&self) -> &'static str {
+        match self {
+            CountAggregation::Monthly(_, _) => "Past month",
+            CountAggregation::Weekly(_, _, _) => "Past week",
+            CountAggregation::Daily(_, _, _) => "Past day",
+            CountAggregation::Yearly(_) => "Past year",
+        }
+    }
+
     /// Returns the current unit value for this aggregation period.
     pub fn unit_str(&self) -> String {
         match self {
@@ -63,9 +73,52 @@ fn month_abbr(m: u32) -> &'static str {
     }
 }
 
+/// How many days back a note can be dated.
+pub const MAX_BACKDATE_DAYS: i64 = 7;
+
 #[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct CountMetadata {
     pub aggregation: CountAggregation,
+    /// When set, the home screen ranks kids by score instead of keeping their order.
+    pub leaderboard: bool,
+    /// First day of the cycle shown.
+    pub period_start: NaiveDate,
+    /// First day after the cycle shown.
+    pub period_end: NaiveDate,
+    /// False for closed cycles, which are shown read-only.
+    pub current: bool,
+    /// Today on the backend's clock, for birthdays and ages.
+    pub today: NaiveDate,
+}
+
+/// Computes competition-style ranks ("1224") for scores already sorted in descending order.
+pub fn competition_ranks(sorted_scores: &[i32]) -> Vec<u32> {
+    let mut ranks = Vec::with_capacity(sorted_scores.len());
+    for (i, score) in sorted_scores.iter().enumerate() {
+        if i > 0 && sorted_scores[i - 1] == *score {
+            ranks.push(ranks[i - 1]);
+        } else {
+            ranks.push(i as u32 + 1);
+        }
+    }
+    ranks
+}
+
+/// Per-family limits applied when adding or renaming kids.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct KidLimits {
+    pub max_kids: u32,
+    /// Counted in characters, not bytes.
+    pub max_name_length: u32,
+}
+
+impl Default for KidLimits {
+    fn default() -> Self {
+        KidLimits {
+            max_kids: 10,
+            max_name_length: 50,
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -75,6 +128,39 @@ pub enum KidsResponseWrapper {
     NoKids,
 }
 
+impl From<GetKidsResponse> for KidsResponseWrapper {
+    fn from(response: GetKidsResponse) -> Self {
+        if response.kids.is_empty() {
+            KidsResponseWrapper::NoKids
+        } else {
+            KidsResponseWrapper::Loaded(response)
+        }
+    }
+}
+
+impl KidsResponseWrapper {
+    /// Adds `delta` to a kid's displayed count, returning the kid as it was before
+    /// so the change can be rolled back.
+    pub fn adjust_count(&mut self, kid_id: u32, delta: i32) -> Option<Kid> {
+        let KidsResponseWrapper::Loaded(data) = self else {
+            return None;
+        };
+        let kid = data.kids.iter_mut().find(|k| k.id == kid_id)?;
+        let previous = kid.clone();
+        kid.count = kid.count.saturating_add(delta);
+        Some(previous)
+    }
+
+    /// Replaces a kid with a newer copy (e.g. the one returned by the server).
+    pub fn replace_kid(&mut self, kid: Kid) {
+        if let KidsResponseWrapper::Loaded(data) = self {
+            if let Some(slot) = data.kids.iter_mut().find(|k| k.id == kid.id) {
+                *slot = kid;
+            }
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct GetKidsResponse {
     pub kids: Vec<Kid>,
@@ -85,12 +171,320 @@ pub struct GetKidsResponse {
 pub struct Kid {
     pub name: String,
     pub id: u32,
-    pub count: i8,
+    pub count: i32,
     pub latest_note: Option<NaiveDateTime>,
+    pub color: Option<String>,
+    pub emoji: Option<String>,
+    pub birthdate: Option<NaiveDate>,
+    /// Set when the kid has an uploaded photo; changes on every upload.
+    pub avatar_version: Option<i64>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct KidSummary {
     pub id: u32,
     pub name: String,
+    pub position: u32,
+    pub color: Option<String>,
+    pub emoji: Option<String>,
+    pub birthdate: Option<NaiveDate>,
+    pub avatar_version: Option<i64>,
+}
+
+/// Pushed to every open app when data changes on another device.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub enum ChangeEvent {
+    /// A note was logged; carries the kid with its updated count.
+    KidCount(Kid),
+    /// A kid was added, edited, reordered, archived, restored or deleted.
+    KidsChanged,
+    /// Aggregation, leaderboard or limits changed.
+    SettingsChanged,
+}
+
+/// Returns the URL of a kid's uploaded photo, if any.
+pub fn avatar_url(kid_id: u32, avatar_version: Option<i64>) -> Option<String> {
+    avatar_version.map(|v| format!("/avatars/{kid_id}?v={v}"))
+}
+
+/// Returns the age in whole years on `today`, or `None` for birthdates in the future.
+pub fn age_on(birthdate: NaiveDate, today: NaiveDate) -> Option<u32> {
+    today.years_since(birthdate)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct KidCycleTotals {
+    pub kid_id: u32,
+    pub name: String,
+    pub color: Option<String>,
+    /// One total per period, aligned with `CycleTotalsResponse::periods`.
+    pub totals: Vec<i32>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CycleTotalsResponse {
+    pub granularity: String,
+    /// Period keys (e.g. "2026-10", "2026-W41"), oldest first.
+    pub periods: Vec<String>,
+    pub kids: Vec<KidCycleTotals>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct KidNoteRatio {
+    pub kid_id: u32,
+    pub name: String,
+    pub color: Option<String>,
+    pub positive: u32,
+    pub negative: u32,
+}
+
+/// Weekday and hour are in the time zone the heatmap was requested for.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct HeatmapCell {
+    /// 0 = Monday ... 6 = Sunday
+    pub weekday: u32,
+    /// Hour of day, 0-23
+    pub hour: u32,
+    pub count: u32,
+}
+
+/// Kinds of change recorded in the audit log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AuditAction {
+    NoteLogged,
+    KidAdded,
+    KidRenamed,
+    KidsReordered,
+    KidProfileUpdated,
+    KidArchived,
+    KidRestored,
+    KidDeleted,
+    AvatarUploaded,
+    AvatarRemoved,
+    GranularityChanged,
+    LeaderboardChanged,
+    LimitsChanged,
+    BackdatingChanged,
+    TimeTravelled,
+    DataImported,
+    BackupRestored,
+    DemoSeeded,
+}
+
+impl AuditAction {
+    pub const ALL: [AuditAction; 18] = [
+        AuditAction::NoteLogged,
+        AuditAction::KidAdded,
+        AuditAction::KidRenamed,
+        AuditAction::KidsReordered,
+        AuditAction::KidProfileUpdated,
+        AuditAction::KidArchived,
+        AuditAction::KidRestored,
+        AuditAction::KidDeleted,
+        AuditAction::AvatarUploaded,
+        AuditAction::AvatarRemoved,
+        AuditAction::GranularityChanged,
+        AuditAction::LeaderboardChanged,
+        AuditAction::LimitsChanged,
+        AuditAction::BackdatingChanged,
+        AuditAction::TimeTravelled,
+        AuditAction::DataImported,
+        AuditAction::BackupRestored,
+        AuditAction::DemoSeeded,
+    ];
+
+    /// The value stored in the database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::NoteLogged => "note_logged",
+            AuditAction::KidAdded => "kid_added",
+            AuditAction::KidRenamed => "kid_renamed",
+            AuditAction::KidsReordered => "kids_reordered",
+            AuditAction::KidProfileUpdated => "kid_profile_updated",
+            AuditAction::KidArchived => "kid_archived",
+            AuditAction::KidRestored => "kid_restored",
+            AuditAction::KidDeleted => "kid_deleted",
+            AuditAction::AvatarUploaded => "avatar_uploaded",
+            AuditAction::AvatarRemoved => "avatar_removed",
+            AuditAction::GranularityChanged => "granularity_changed",
+            AuditAction::LeaderboardChanged => "leaderboard_changed",
+            AuditAction::LimitsChanged => "limits_changed",
+            AuditAction::BackdatingChanged => "backdating_changed",
+            AuditAction::TimeTravelled => "time_travelled",
+            AuditAction::DataImported => "data_imported",
+            AuditAction::BackupRestored => "backup_restored",
+            AuditAction::DemoSeeded => "demo_seeded",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<AuditAction> {
+        AuditAction::ALL.into_iter().find(|a| a.as_str() == value)
+    }
+
+    /// Human-readable name for the audit view.
+    pub fn label(&self) -> &'static str {
+        match self {
+            AuditAction::NoteLogged => "Note",
+            AuditAction::KidAdded => "Kid added",
+            AuditAction::KidRenamed => "Kid renamed",
+            AuditAction::KidsReordered => "Kids reordered",
+            AuditAction::KidProfileUpdated => "Profile updated",
+            AuditAction::KidArchived => "Kid archived",
+            AuditAction::KidRestored => "Kid restored",
+            AuditAction::KidDeleted => "Kid deleted",
+            AuditAction::AvatarUploaded => "Photo uploaded",
+            AuditAction::AvatarRemoved => "Photo removed",
+            AuditAction::GranularityChanged => "Aggregation changed",
+            AuditAction::LeaderboardChanged => "Leaderboard changed",
+            AuditAction::LimitsChanged => "Limits changed",
+            AuditAction::BackdatingChanged => "Backdating changed",
+            AuditAction::TimeTravelled => "Time travel",
+            AuditAction::DataImported => "Data imported",
+            AuditAction::BackupRestored => "Backup restored",
+            AuditAction::DemoSeeded => "Demo data added",
+        }
+    }
+}
+
+/// One row of the append-only audit log.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: u32,
+    /// Where the change came from ("web", "app", ...); there are no user accounts.
+    pub actor: String,
+    pub action: AuditAction,
+    pub kid_id: Option<u32>,
+    /// The kid's name when the change was made, kept after the kid is deleted.
+    pub kid_name: Option<String>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+/// Version of the export format, bumped whenever its shape changes.
+pub const EXPORT_FORMAT_VERSION: u32 = 1;
+
+/// A kid as exported, archived ones included.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct KidRecord {
+    pub id: u32,
+    pub name: String,
+    pub position: u32,
+    pub color: Option<String>,
+    pub emoji: Option<String>,
+    pub birthdate: Option<NaiveDate>,
+    pub archived: bool,
+}
+
+/// A single note as stored. Notes carry a +1/-1 quantity only; there is no reason text.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct NoteRecord {
+    pub kid_id: u32,
+    pub quantity: i32,
+    pub occurred_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+    pub idempotency_key: Option<String>,
+}
+
+/// The family settings, flattened so they fit a single CSV row.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SettingsRecord {
+    pub granularity: String,
+    pub leaderboard: bool,
+    pub backdate_closed_cycles: bool,
+    pub max_kids: u32,
+    pub max_name_length: u32,
+}
+
+/// A kid's total over one cycle of the configured granularity. Only cycles in which
+/// the kid has notes are listed.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CycleRecord {
+    /// Period key, as on the stats charts (e.g. "2026-10", "2026-W41").
+    pub period: String,
+    pub start: NaiveDate,
+    /// First day after the cycle.
+    pub end: NaiveDate,
+    pub kid_id: u32,
+    pub total: i32,
+}
+
+/// Everything the household has stored, as one document.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct HouseholdExport {
+    pub format_version: u32,
+    pub exported_at: NaiveDateTime,
+    pub settings: SettingsRecord,
+    pub kids: Vec<KidRecord>,
+    pub notes: Vec<NoteRecord>,
+    /// Derived from the notes at export time.
+    pub cycles: Vec<CycleRecord>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ExportFormat {
+    /// A single JSON document.
+    Json,
+    /// A zip with one CSV file per table.
+    CsvZip,
+}
+
+/// A file ready to be downloaded.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ExportFile {
+    pub file_name: String,
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+}
+
+/// What a kid report covers, regardless of the configured granularity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ReportPeriod {
+    /// Monday to Sunday.
+    Week,
+    Month,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ReportFormat {
+    /// A standalone page meant to be printed from the browser.
+    Html,
+    Pdf,
+}
+
+/// Largest file accepted for import.
+pub const MAX_IMPORT_BYTES: usize = 20 * 1024 * 1024;
+
+/// What an import did, or would do for a dry run.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ImportPreview {
+    /// Problems found in the file. Nothing is imported while there are any.
+    pub errors: Vec<String>,
+    /// Kids in the file that already exist (matched by name) and receive their notes.
+    pub kids_matched: Vec<String>,
+    pub kids_added: Vec<String>,
+    pub notes_added: u32,
+    /// Notes whose idempotency key is already stored, e.g. from an earlier import.
+    pub notes_skipped: u32,
+    /// False for a dry run.
+    pub committed: bool,
+}
+
+/// A stored backup, as listed on the settings screen.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BackupInfo {
+    pub name: String,
+    pub size: u64,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BackupStatus {
+    /// Where backups go, e.g. "s3://bucket/prefix"; `None` when backups are off.
+    pub target: Option<String>,
+    pub interval_hours: u64,
+    /// How many backups are kept before the oldest are deleted.
+    pub keep: u32,
+    /// Newest first.
+    pub backups: Vec<BackupInfo>,
 }
@@ -1,35 +1,177 @@
-use crate::backend::kids::{decrement_kid_count, get_kids, increment_kid_count};
+use crate::backend::api::{Api, Backend};
+use crate::backend::events::subscribe_changes;
 use crate::components::{button::*, kid_card::*};
-use crate::models::KidsResponseWrapper;
+use crate::models::{competition_ranks, ChangeEvent, KidsResponseWrapper, MAX_BACKDATE_DAYS};
+use crate::offline::{self, QueuedNote};
 use crate::Route;
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeDelta, TimeZone, Utc};
 use dioxus::prelude::*;
 use dioxus_primitives::toast::{consume_toast, ToastOptions};
 use std::time::Duration;
+use uuid::Uuid;
 
 #[component]
 pub fn NoticaApp() -> Element {
-    let mut kids = use_signal(|| KidsResponseWrapper::Loading);
+    // Cycle being browsed; `None` follows the current one
+    let mut period: Signal<Option<NaiveDate>> = use_signal(|| None);
+    // When set, taps are recorded as having happened at this time
+    let mut backdate: Signal<Option<NaiveDateTime>> = use_signal(|| None);
 
-    let mut rs = use_resource(move || async move {
-        match get_kids().await {
-            Ok(k) => {
-                if k.kids.is_empty() {
-                    kids.set(KidsResponseWrapper::NoKids);
-                } else {
-                    kids.set(KidsResponseWrapper::Loaded(k));
+    // Resolved during server rendering and hydrated on the client, so the first
+    // paint already shows the counts
+    let mut rs = use_server_future(move || Api::get_kids(period()))?;
+
+    // Local copy that taps update optimistically, seeded with the rendered data
+    let mut kids = use_signal(move || match &*rs.peek() {
+        Some(Ok(k)) => KidsResponseWrapper::from(k.clone()),
+        _ => KidsResponseWrapper::Loading,
+    });
+
+    // Follow every reload of the resource (after reconnecting, on pushed changes, ...)
+    use_effect(move || match &*rs.read() {
+        Some(Ok(k)) => {
+            if k.count_metadata.current {
+                offline::cache_kids(k);
+            }
+            kids.set(KidsResponseWrapper::from(k.clone()));
+        }
+        Some(Err(e)) if offline::is_offline_error(e) => {
+            // Show the last known counts until the connection comes back
+            spawn(async move {
+                if let Some(cached) = offline::load_cached_kids().await {
+                    kids.set(KidsResponseWrapper::from(cached));
+                }
+            });
+            let toast = consume_toast();
+            toast.info(
+                "You are offline".to_string(),
+                ToastOptions::new()
+                    .description("Showing the last known counts".to_string())
+                    .duration(Duration::from_secs(5)),
+            );
+        }
+        Some(Err(e)) => {
+            let toast = consume_toast();
+            toast.error(
+                "Failed to load kids".to_string(),
+                ToastOptions::new()
+                    .description(format!("{e}"))
+                    .duration(Duration::from_secs(5)),
+            );
+        }
+        None => {}
+    });
+
+    // Replay notes taken offline, once at start and again on every reconnection
+    use_future(move || async move {
+        let mut online = offline::online_events();
+        loop {
+            let replay = offline::replay_queued_notes().await;
+            if replay.sent > 0 {
+                rs.restart();
+            }
+            if let Some(reason) = replay.dropped.first() {
+                let toast = consume_toast();
+                toast.error(
+                    match replay.dropped.len() {
+                        1 => "A note taken offline was not saved".to_string(),
+                        n => format!("{n} notes taken offline were not saved"),
+                    },
+                    ToastOptions::new()
+                        .description(reason.clone())
+                        .duration(Duration::from_secs(5)),
+                );
+            }
+            if online.recv::<bool>().await.is_err() {
+                break;
+            }
+        }
+    });
+
+    // Live updates from the other parents' devices
+    use_future(move || async move {
+        let mut online = offline::online_events();
+        loop {
+            if let Ok(mut changes) = subscribe_changes().await {
+                while let Some(Ok(event)) = changes.recv().await {
+                    match event {
+                        // Closed cycles don't change with new notes
+                        ChangeEvent::KidCount(kid) => {
+                            if showing_current_cycle(&kids.peek()) {
+                                kids.write().replace_kid(kid);
+                            }
+                        }
+                        ChangeEvent::KidsChanged | ChangeEvent::SettingsChanged => rs.restart(),
+                    }
+                }
+            }
+            // The stream dropped; catch up and resubscribe once we're back online
+            if online.recv::<bool>().await.is_err() {
+                break;
+            }
+            rs.restart();
+        }
+    });
+
+    // Shows the tap right away, then settles it with the kid returned by the server.
+    // Taps that can't reach the server are queued; rejected ones are rolled back.
+    let record_note = move |kid_id: u32, add: bool| async move {
+        let delta = if add { 1 } else { -1 };
+        // The picker shows the device's wall-clock time; the server stores UTC
+        let occurred_at = *backdate.peek();
+        let offset = occurred_at.map_or(0, utc_offset_at);
+        // Notes backdated into an earlier cycle don't change the counts shown
+        let optimistic = occurred_at.is_none_or(|at| {
+            counts_in_shown_cycle(&kids.peek(), at - TimeDelta::minutes(offset as i64))
+        });
+        if optimistic && kids.write().adjust_count(kid_id, delta).is_none() {
+            return;
+        }
+        let key = Uuid::new_v4().to_string();
+        let result = if add {
+            Api::increment_kid_count(kid_id, Some(key.clone()), occurred_at, offset).await
+        } else {
+            Api::decrement_kid_count(kid_id, Some(key.clone()), occurred_at, offset).await
+        };
+
+        match result {
+            Ok(kid) => kids.write().replace_kid(kid),
+            Err(e) if offline::is_offline_error(&e) => {
+                if let KidsResponseWrapper::Loaded(data) = &*kids.read() {
+                    offline::cache_kids(data);
                 }
+                // Stamped now so the note keeps the time of the tap, not of the replay
+                offline::queue_note(QueuedNote {
+                    kid_id,
+                    add,
+                    idempotency_key: key,
+                    occurred_at: occurred_at.or_else(|| Some(Utc::now().naive_utc())),
+                    utc_offset_minutes: offset,
+                })
+                .await;
+                let toast = consume_toast();
+                toast.info(
+                    "Saved offline".to_string(),
+                    ToastOptions::new()
+                        .description("It will be sent when you're back online".to_string())
+                        .duration(Duration::from_secs(3)),
+                );
             }
             Err(e) => {
+                // Undo only this tap; others may have landed in the meantime
+                if optimistic {
+                    kids.write().adjust_count(kid_id, -delta);
+                }
                 let toast = consume_toast();
                 toast.error(
-                    "Failed to load kids".to_string(),
+                    if add { "Failed to add note" } else { "Failed to remove note" }.to_string(),
                     ToastOptions::new()
                         .description(format!("{e}"))
                         .duration(Duration::from_secs(5)),
                 );
             }
         }
-    });
+    };
 
     let kids_snapshot = kids.read().clone();
 
@@ -84,46 +226,70 @@ pub fn NoticaApp() -> Element {
                 let aggregation = &data.count_metadata.aggregation;
                 let agg_label = aggregation.label();
                 let agg_unit = aggregation.unit_str();
-                let agg_unit_label = aggregation.unit_label();
+                let is_current = data.count_metadata.current;
+                let agg_unit_label = if is_current {
+                    aggregation.unit_label()
+                } else {
+                    aggregation.past_unit_label()
+                };
+                // Any day of the neighbouring cycles will do
+                let previous = data.count_metadata.period_start.pred_opt();
+                let next = (!is_current).then_some(data.count_metadata.period_end);
+                let today = data.count_metadata.today;
+
+                // Leaderboard mode ranks kids by score; otherwise keep the server's stable order
+                let mut kid_list = data.kids;
+                let ranks: Vec<Option<u32>> = if data.count_metadata.leaderboard {
+                    kid_list.sort_by(|a, b| b.count.cmp(&a.count));
+                    let scores: Vec<i32> = kid_list.iter().map(|k| k.count).collect();
+                    competition_ranks(&scores).into_iter().map(Some).collect()
+                } else {
+                    vec![None; kid_list.len()]
+                };
+
+                let backdate_value = backdate()
+                    .map(|at| at.format("%Y-%m-%dT%H:%M").to_string())
+                    .unwrap_or_default();
 
                 rsx! {
+                    // ── Backdating: when the next taps happened ──
+                    if is_current {
+                        div { style: "display: flex; align-items: center; gap: 0.5rem; margin-bottom: 0.75rem; font-size: 0.8125rem; color: #6b7280;",
+                            span { "Happened" }
+                            input {
+                                style: "border-radius: 0.5rem; border: 1px solid #e5e7eb; background: white; padding: 0.25rem 0.5rem; font-size: 0.8125rem; outline: none;",
+                                r#type: "datetime-local",
+                                title: "Leave empty for now; up to {MAX_BACKDATE_DAYS} days back",
+                                value: "{backdate_value}",
+                                oninput: move |e: Event<FormData>| {
+                                    backdate.set(NaiveDateTime::parse_from_str(&e.value(), "%Y-%m-%dT%H:%M").ok());
+                                },
+                            }
+                            if backdate().is_some() {
+                                button {
+                                    style: "border: none; background: transparent; color: #2563eb; cursor: pointer; font-size: 0.8125rem;",
+                                    onclick: move |_| backdate.set(None),
+                                    "Now"
+                                }
+                            }
+                        }
+                    }
+
                     // ── Kid cards ──
                     div { style: "display: flex; flex-direction: column; gap: 0.75rem;",
                         {
-                            data.kids
+                            kid_list
                                 .into_iter()
-                                .map(|kid| {
+                                .zip(ranks)
+                                .map(|(kid, rank)| {
                                     rsx! {
                                         KidCard {
                                             kid,
-                                            on_increment: move |kid_id: u32| async move {
-                                                match increment_kid_count(kid_id).await {
-                                                    Ok(_) => rs.restart(),
-                                                    Err(e) => {
-                                                        let toast = consume_toast();
-                                                        toast.error(
-                                                            "Failed to add note".to_string(),
-                                                            ToastOptions::new()
-                                                                .description(format!("{e}"))
-                                                                .duration(Duration::from_secs(5)),
-                                                        );
-                                                    }
-                                                }
-                                            },
-                                            on_decrement: move |kid_id: u32| async move {
-                                                match decrement_kid_count(kid_id).await {
-                                                    Ok(_) => rs.restart(),
-                                                    Err(e) => {
-                                                        let toast = consume_toast();
-                                                        toast.error(
-                                                            "Failed to remove note".to_string(),
-                                                            ToastOptions::new()
-                                                                .description(format!("{e}"))
-                                                                .duration(Duration::from_secs(5)),
-                                                        );
-                                                    }
-                                                }
-                                            },
+                                            rank,
+                                            today,
+                                            read_only: !is_current,
+                                            on_increment: move |kid_id: u32| record_note(kid_id, true),
+                                            on_decrement: move |kid_id: u32| record_note(kid_id, false),
                                         }
                                     }
                                 })
@@ -143,12 +309,40 @@ pub fn NoticaApp() -> Element {
                                     }
                                 }
                                 div { style: "width: 1px; height: 1.75rem; background-color: #e5e7eb;" }
-                                div {
-                                    p { style: "font-size: 0.625rem; font-weight: 500; text-transform: uppercase; letter-spacing: 0.05em; color: #9ca3af;",
-                                        "{agg_unit_label}"
+                                div { style: "display: flex; align-items: center; gap: 0.5rem;",
+                                    button {
+                                        style: "border: none; background: transparent; padding: 0.25rem; font-size: 1rem; color: #6b7280; cursor: pointer;",
+                                        title: "Previous cycle",
+                                        disabled: previous.is_none(),
+                                        onclick: move |_| {
+                                            if let Some(day) = previous {
+                                                period.set(Some(day));
+                                            }
+                                        },
+                                        "‹"
                                     }
-                                    p { style: "font-size: 0.875rem; font-weight: 600; color: #374151; margin-top: 1px;",
-                                        "{agg_unit}"
+                                    div {
+                                        p { style: "font-size: 0.625rem; font-weight: 500; text-transform: uppercase; letter-spacing: 0.05em; color: #9ca3af;",
+                                            "{agg_unit_label}"
+                                        }
+                                        p { style: "font-size: 0.875rem; font-weight: 600; color: #374151; margin-top: 1px;",
+                                            "{agg_unit}"
+                                        }
+                                    }
+                                    button {
+                                        style: if next.is_some() {
+                                            "border: none; background: transparent; padding: 0.25rem; font-size: 1rem; color: #6b7280; cursor: pointer;"
+                                        } else {
+                                            "border: none; background: transparent; padding: 0.25rem; font-size: 1rem; color: #e5e7eb; cursor: default;"
+                                        },
+                                        title: "Next cycle",
+                                        disabled: next.is_none(),
+                                        onclick: move |_| {
+                                            if let Some(day) = next {
+                                                period.set(Some(day));
+                                            }
+                                        },
+                                        "›"
                                     }
                                 }
                             }
@@ -176,6 +370,11 @@ pub fn NoticaApp() -> Element {
                                 }
                                 "Settings"
                             }
+                            Link {
+                                to: Route::StatsView,
+                                style: "display: flex; align-items: center; padding: 0.5rem 0.5rem; border-radius: 0.5rem; font-size: 0.8125rem; font-weight: 500; color: #9ca3af; text-decoration: none; transition: all 0.15s;",
+                                "Stats"
+                            }
                             Link {
                                 to: Route::AboutView,
                                 style: "display: flex; align-items: center; padding: 0.5rem 0.5rem; border-radius: 0.5rem; font-size: 0.8125rem; font-weight: 500; color: #9ca3af; text-decoration: none; transition: all 0.15s;",
@@ -195,3 +394,30 @@ pub fn NoticaApp() -> Element {
         }
     }
 }
+
+/// Minutes the device's time zone is ahead of UTC at the local time `at`, which may
+/// be on the other side of a DST change from now.
+fn utc_offset_at(at: NaiveDateTime) -> i32 {
+    let seconds = match Local.from_local_datetime(&at).earliest() {
+        Some(local) => local.offset().local_minus_utc(),
+        // Skipped by a DST change; the offset now is close enough
+        None => Local::now().offset().local_minus_utc(),
+    };
+    seconds / 60
+}
+
+fn counts_in_shown_cycle(kids: &KidsResponseWrapper, at: NaiveDateTime) -> bool {
+    match kids {
+        KidsResponseWrapper::Loaded(data) => {
+            data.count_metadata.period_start <= at.date() && at.date() < data.count_metadata.period_end
+        }
+        _ => false,
+    }
+}
+
+fn showing_current_cycle(kids: &KidsResponseWrapper) -> bool {
+    match kids {
+        KidsResponseWrapper::Loaded(data) => data.count_metadata.current,
+        _ => true,
+    }
+}
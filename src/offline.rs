@@ -0,0 +1,131 @@
+//! Client-side offline support: the last `get_kids` result and note taps made
+//! without a connection are kept in `localStorage`, and queued notes are replayed
+//! with their original idempotency keys once the browser is back online.
+use crate::backend::api::{Api, Backend};
+use crate::models::GetKidsResponse;
+use chrono::NaiveDateTime;
+use dioxus::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+const KIDS_CACHE_KEY: &str = "hot_dog.kids";
+const NOTE_QUEUE_KEY: &str = "hot_dog.note_queue";
+
+/// A +1/-1 tap that could not reach the server.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueuedNote {
+    pub kid_id: u32,
+    pub add: bool,
+    pub idempotency_key: String,
+    /// When the tap happened, or the time it was backdated to. Missing from notes
+    /// queued by older versions, which then count from when they are replayed.
+    #[serde(default)]
+    pub occurred_at: Option<NaiveDateTime>,
+    /// Minutes ahead of UTC that `occurred_at` is given in: the device's time zone for
+    /// backdated notes, 0 for the time of the tap.
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+}
+
+/// What a replay of the queue did.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Replay {
+    pub sent: usize,
+    /// Why each rejected note was dropped, in queue order.
+    pub dropped: Vec<String>,
+}
+
+async fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    document::eval(&format!(
+        "const raw = localStorage.getItem('{key}'); return raw === null ? null : JSON.parse(raw);"
+    ))
+    .join::<Option<T>>()
+    .await
+    .ok()
+    .flatten()
+}
+
+fn store<T: Serialize>(key: &str, value: &T) {
+    let eval = document::eval(&format!(
+        "localStorage.setItem('{key}', JSON.stringify(await dioxus.recv()));"
+    ));
+    let _ = eval.send(value);
+}
+
+/// Returns true when the error means the server could not be reached at all,
+/// as opposed to the server rejecting the call.
+pub fn is_offline_error(e: &ServerFnError) -> bool {
+    matches!(e, ServerFnError::Request(_))
+}
+
+pub async fn load_cached_kids() -> Option<GetKidsResponse> {
+    load(KIDS_CACHE_KEY).await
+}
+
+pub fn cache_kids(kids: &GetKidsResponse) {
+    store(KIDS_CACHE_KEY, kids);
+}
+
+pub async fn queued_notes() -> Vec<QueuedNote> {
+    load(NOTE_QUEUE_KEY).await.unwrap_or_default()
+}
+
+pub async fn queue_note(note: QueuedNote) {
+    let mut queue = queued_notes().await;
+    queue.push(note);
+    store(NOTE_QUEUE_KEY, &queue);
+}
+
+/// Sends queued notes in the order they were taken. Stops at the first one that
+/// still can't reach the server; notes the server rejects are dropped since
+/// retrying them would never succeed, and reported back.
+pub async fn replay_queued_notes() -> Replay {
+    let queue = queued_notes().await;
+    let mut sent = Vec::new();
+    let mut dropped = Vec::new();
+    let mut reasons = Vec::new();
+
+    for note in &queue {
+        let key = Some(note.idempotency_key.clone());
+        let result = if note.add {
+            Api::increment_kid_count(note.kid_id, key, note.occurred_at, note.utc_offset_minutes)
+                .await
+        } else {
+            Api::decrement_kid_count(note.kid_id, key, note.occurred_at, note.utc_offset_minutes)
+                .await
+        };
+        match result {
+            Ok(_) => sent.push(note.idempotency_key.clone()),
+            Err(e) if is_offline_error(&e) => break,
+            Err(e) => {
+                dropped.push(note.idempotency_key.clone());
+                reasons.push(e.to_string());
+            }
+        }
+    }
+
+    if !sent.is_empty() || !dropped.is_empty() {
+        // Re-read so taps queued while we were replaying are kept
+        let remaining: Vec<QueuedNote> = queued_notes()
+            .await
+            .into_iter()
+            .filter(|n| !sent.contains(&n.idempotency_key) && !dropped.contains(&n.idempotency_key))
+            .collect();
+        store(NOTE_QUEUE_KEY, &remaining);
+    }
+    Replay {
+        sent: sent.len(),
+        dropped: reasons,
+    }
+}
+
+/// Listens for the browser's `online` event. Each reconnection yields one message.
+pub fn online_events() -> document::Eval {
+    document::eval("window.addEventListener('online', () => dioxus.send(true));")
+}
+
+/// Registers the service worker so the app shell loads without a connection.
+pub fn register_service_worker() {
+    document::eval(
+        "if ('serviceWorker' in navigator) { navigator.serviceWorker.register('/sw.js'); }",
+    );
+}